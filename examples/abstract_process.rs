@@ -5,6 +5,7 @@ cached_process! {
     static COUNTER_ABSTRACT_PROCESS: ProcessRef<Counter> = "counter-abstract-process";
 }
 
+#[allow(dead_code)]
 struct Counter(i32);
 
 impl AbstractProcess for Counter {
@@ -6,7 +6,12 @@ cached_process! {
 }
 
 fn main() {
-    let process = spawn_link!(|_mailbox: Mailbox<()>| { loop {} });
+    let process = spawn_link!(|mailbox: Mailbox<()>| {
+        #[allow(unreachable_code)]
+        loop {
+            mailbox.receive();
+        }
+    });
     process.register("counter-process");
 
     let lookup: Option<Process<()>> = COUNTER_PROCESS.get(); // First call lookup process from host
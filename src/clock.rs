@@ -0,0 +1,77 @@
+use std::{
+    cell::Cell,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Source of the current time used by a [`CachedProcess`](crate::CachedProcess) to expire lookups.
+///
+/// Caches use [`SystemClock`] by default. A [`ManualClock`] can be set with
+/// [`CachedProcess::with_clock`](crate::CachedProcess::with_clock) to test expiry deterministically.
+pub trait Clock: fmt::Debug {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// Clock reading the time from the lunatic runtime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which only moves forward when advanced.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lunatic_cached_process::{CachedLookup, CachedProcess, ManualClock, ProcessCached};
+///
+/// let clock = ManualClock::new();
+/// let cache: ProcessCached<'_, ()> = CachedProcess::builder("foo")
+///     .negative_ttl(Duration::from_secs(5))
+///     .clock(&clock)
+///     .build();
+///
+/// assert!(cache.get().is_none());
+/// clock.advance(Duration::from_secs(5)); // The failed lookup has expired
+/// assert!(cache.get().is_none());
+/// assert_eq!(cache.stats().misses, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Cell<Duration>,
+}
+
+impl ManualClock {
+    /// Construct a new clock starting at the current time.
+    pub fn new() -> Self {
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::ZERO),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+}
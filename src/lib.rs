@@ -6,283 +6,4026 @@
 //! # Example
 //!
 //! ```
-//! use lunatic::{spawn_link, test};
+//! use lunatic::{spawn_link, Process};
 //! use lunatic_cached_process::{cached_process, CachedLookup};
 //!
 //! cached_process! {
 //!     static COUNTER_PROCESS: Process<()> = "counter-process";
 //! }
 //!
-//! let process = spawn_link!(|mailbox: Mailbox<()>| { loop { } });
+//! let process = spawn_link!(|mailbox: Mailbox<()>| { loop { mailbox.receive(); } });
 //! process.register("counter-process");
 //!
-//! let lookup: Option<Process<T>> = COUNTER_PROCESS.get(); // First call will lookup process from lunatic runtime
+//! let lookup: Option<Process<()>> = COUNTER_PROCESS.get(); // First call will lookup process from lunatic runtime
 //! assert!(lookup.is_some());
 //!
-//! let lookup: Option<Process<T>> = COUNTER_PROCESS.get(); // Subsequent calls will use cached lookup
+//! let lookup: Option<Process<()>> = COUNTER_PROCESS.get(); // Subsequent calls will use cached lookup
 //! assert!(lookup.is_some());
 //! ```
+//!
+//! # Features
+//!
+//! - `async`: Adds [`CachedLookup::get_async`] for looking up processes from async code.
+//! - `cached`: Implements the [`cached::Cached`](https://docs.rs/cached/latest/cached/trait.Cached.html) trait
+//!   for [`DynamicCachedProcess`], keyed by process name with the process handle as the value.
+//! - `serialize-name-only`: Skips the cached process when serializing a [`CachedProcess`],
+//!   so only the process name and configuration are sent. A deserialized cache is always not looked up either way.
+//! - `no-cache`: Turns every cache into a pass-through, where each lookup looks up the process from the lunatic runtime
+//!   and the result is never cached. This is useful for checking whether a bug is caused by a stale cached process.
+//!   Values stored explicitly with [`CachedLookup::set`] are still kept, so [`CachedLookup::peek`] returns them,
+//!   but lookups ignore them.
+//! - `tracing`: Logs cache hits and misses at the debug level with [tracing](https://crates.io/crates/tracing),
+//!   including the process name and whether the process was present.
+
+use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use lunatic::{process::ProcessRef, serializer::Bincode, Process, ProcessLocal, Tag};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use std::cell::RefCell;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use sharded::{
+    CacheSet, CachedProcessArray, CachedProcessGroup, DynamicCachedProcess, DynamicProcessCached,
+    DynamicProcessRefCached, ShardedCachedProcess, ShardedProcessCached, ShardedProcessRefCached,
+};
+pub use shared::{
+    start_cache_server, CacheServer, SharedCachedProcess, SharedProcessCached,
+    SharedProcessRefCached,
+};
 
-use lunatic::{process::ProcessRef, serializer::Bincode, Process, ProcessLocal};
-use serde::{Deserialize, Serialize};
+mod clock;
+mod sharded;
+mod shared;
 
 /// This is used internally for the cached_process! macro.
 #[doc(hidden)]
 pub use paste::paste;
 
+/// Cache of a [`Process`] looked up by name.
 pub type ProcessCached<'a, T, S = Bincode> = CachedProcess<'a, Process<T, S>>;
+/// Cache of a [`ProcessRef`] looked up by name.
+///
+/// The cached [`ProcessRef`] is the handle requests and messages are sent through,
+/// so the result of [`CachedLookup::get`] can be used to call the process handlers directly.
 pub type ProcessRefCached<'a, T> = CachedProcess<'a, ProcessRef<T>>;
 
 /// Cached process to avoid looking up a global process multiple times.
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// A deserialized cache is always not looked up, so a process handle resolved by the sender isn't used by the receiver.
+/// Only plain configuration is kept: the process name, the ttl, negative ttl and max negative ttl, retries, backoff,
+/// double check and node id. Configuration which borrows data or is set by fn pointer can't be sent to another process,
+/// so fallback names, the resolution order, the clock, the resolver, the name transform, the liveness check and the hooks
+/// are reset to their defaults, as are prewarm and runtime state such as stats, links and whether the cache is disabled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(
+    not(feature = "serialize-name-only"),
+    serde(bound(deserialize = "T: Deserialize<'de>"))
+)]
+#[cfg_attr(feature = "serialize-name-only", serde(bound(deserialize = "")))]
 pub struct CachedProcess<'a, T> {
     // TODO: Replace with `Cell` when lunatic gets a new version where `ProcessRef` is `Copy`.
+    #[cfg_attr(
+        feature = "serialize-name-only",
+        serde(skip, default = "not_looked_up")
+    )]
+    #[cfg_attr(
+        not(feature = "serialize-name-only"),
+        serde(deserialize_with = "deserialize_not_looked_up")
+    )]
     lookup_state: RefCell<LookupState<T>>,
-    process_name: &'a str,
+    process_name: ProcessName<'a>,
+    #[serde(skip)]
+    fallback_names: &'a [&'a str],
+    ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    max_negative_ttl: Option<Duration>,
+    retries: u32,
+    backoff: Option<Duration>,
+    double_check: bool,
+    node_id: Option<u64>,
+    #[serde(skip)]
+    resolution_order: &'a [ResolutionSource],
+    /// The source which accepted the process found by the last lookup.
+    #[serde(skip)]
+    resolved_source: Cell<Option<ResolutionSource>>,
+    #[serde(skip)]
+    liveness_check: Option<fn(&T) -> bool>,
+    #[serde(skip)]
+    on_dead: Option<fn(&str)>,
+    #[serde(skip)]
+    resolver: Option<fn(&str) -> Option<T>>,
+    #[serde(skip)]
+    name_transform: Option<fn(&str) -> String>,
+    #[serde(skip)]
+    on_transition: Option<fn(&str, CacheState, CacheState)>,
+    #[serde(skip)]
+    prewarm: bool,
+    #[serde(skip)]
+    stats: Cell<CacheStats>,
+    #[serde(skip)]
+    last_was_hit: Cell<Option<bool>>,
+    #[serde(skip)]
+    clock: Option<&'a dyn Clock>,
+    #[serde(skip)]
+    disabled: Cell<bool>,
+    /// The tag and id of the process linked with [`CachedLookup::get_linked`].
+    #[serde(skip)]
+    link: Cell<Option<(Tag, u64)>>,
+    /// Whether the resolver is running, to reject lookups made by the resolver itself.
+    #[serde(skip)]
+    resolving: Cell<bool>,
+    /// Metadata fetched by [`CachedProcess::get_with_meta`] for the cached process.
+    #[serde(skip)]
+    meta: RefCell<Option<Meta>>,
 }
 
 impl<'a, T> CachedProcess<'a, T> {
     /// Construct a new process cache with a registered process name.
-    pub fn new(name: &'a str) -> Self {
+    ///
+    /// The name can be borrowed, or owned for names which are computed at runtime.
+    /// See [`CachedProcess::builder`] for configuring the cache.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
         CachedProcess {
-            lookup_state: RefCell::new(LookupState::NotLookedUp),
-            process_name: name,
+            process_name: ProcessName::new(Some(name.into())),
+            ..CachedProcess::new_const("")
         }
     }
 
-    /// Returns the process name.
-    pub fn process_name(&'a self) -> &'a str {
-        self.process_name
-    }
-
-    /// Returns true if the process has been looked up and exists.
+    /// Construct a new process cache with a borrowed process name in a const context.
+    ///
+    /// This allows arrays of caches to be declared without the [`cached_process!`] macro.
+    /// Note that a `CachedProcess` isn't `Sync`, so it can't be used in a `static` directly.
     ///
     /// # Example
     ///
     /// ```
-    /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedLookup, CachedProcess, ProcessCached};
+    ///
+    /// lunatic::process_local! {
+    ///     static SHARDS: [ProcessCached<'static, ()>; 2] = [
+    ///         CachedProcess::new_const("shard-0"),
+    ///         CachedProcess::new_const("shard-1"),
+    ///     ];
+    /// }
+    ///
+    /// assert!(SHARDS.with(|shards| shards[0].get()).is_none());
+    /// ```
+    pub const fn new_const(name: &'a str) -> Self {
+        CachedProcess {
+            lookup_state: RefCell::new(LookupState::NotLookedUp),
+            process_name: ProcessName::new(Some(Cow::Borrowed(name))),
+            fallback_names: &[],
+            ttl: None,
+            negative_ttl: None,
+            max_negative_ttl: None,
+            retries: 0,
+            backoff: None,
+            double_check: false,
+            node_id: None,
+            resolution_order: &[],
+            resolved_source: Cell::new(None),
+            liveness_check: None,
+            on_dead: None,
+            resolver: None,
+            name_transform: None,
+            on_transition: None,
+            prewarm: false,
+            stats: Cell::new(CacheStats {
+                hits: 0,
+                misses: 0,
+                absent: 0,
+                last_lookup_duration: None,
+            }),
+            last_was_hit: Cell::new(None),
+            clock: None,
+            disabled: Cell::new(false),
+            link: Cell::new(None),
+            resolving: Cell::new(false),
+            meta: RefCell::new(None),
+        }
+    }
+
+    /// Construct a new builder for configuring a process cache with a registered process name.
+    pub fn builder(name: impl Into<Cow<'a, str>>) -> CachedProcessBuilder<'a, T> {
+        CachedProcessBuilder::new(name)
+    }
+
+    /// Construct a new process cache with a registered process name,
+    /// where a found process is only cached for the duration of `ttl`.
     ///
-    /// let process: CachedProcess<'static, Process<()>> = CachedProcess::new("foo");
-    /// assert!(!process.is_present()); // Initially not present
+    /// Once the cached process is older than `ttl`, the next call to [`CachedLookup::get`]
+    /// will lookup the process again. This also applies to values stored with [`CachedLookup::set`].
+    pub fn with_ttl(name: impl Into<Cow<'a, str>>, ttl: Duration) -> Self {
+        CachedProcess {
+            ttl: Some(ttl),
+            ..CachedProcess::new(name)
+        }
+    }
+
+    /// Construct a new process cache which looks up each of the registered process names in order,
+    /// using the first one which is found.
     ///
-    /// process.get();
-    /// assert!(!process.is_present()); // Not present, even after lookup
+    /// The name which was found is cached along with the process, and can be retrieved with [`CachedProcess::resolved_name`].
     ///
-    /// spawn!(|| { loop { /* ... */ } }).register("foo"); // Start a process called "foo"
+    /// # Panics
     ///
-    /// process.reset();
-    /// process.get();
-    /// assert!(process.is_present()); // Is present
-    /// ```
-    pub fn is_present(&'a self) -> bool {
-        matches!(&*self.lookup_state.borrow(), LookupState::Present(_))
+    /// Panics if `names` is empty.
+    pub fn new_with_fallbacks(names: &'a [&'a str]) -> Self {
+        let (name, fallback_names) = names
+            .split_first()
+            .expect("at least one process name is required");
+        CachedProcess {
+            fallback_names,
+            ..CachedProcess::new(*name)
+        }
     }
 
-    /// Returns true if the process has been looked up, regardless if the process was found.
+    /// Construct a new process cache which looks up processes with `resolver` instead of the lunatic registry.
+    ///
+    /// This is useful for testing code which uses cached lookups without registering processes.
     ///
     /// # Example
     ///
     /// ```
     /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedLookup, CachedProcess, ProcessCached};
     ///
-    /// let process: CachedProcess<'static, Process<()>> = CachedProcess::new("");
-    /// assert!(!process.is_looked_up());
-    ///
-    /// process.get();
-    /// assert!(process.is_looked_up());
+    /// let cache: ProcessCached<'_, ()> = CachedProcess::with_resolver("foo", |_| Some(Process::this()));
+    /// assert_eq!(cache.get(), Some(Process::this()));
     /// ```
-    pub fn is_looked_up(&'a self) -> bool {
-        matches!(&*self.lookup_state.borrow(), LookupState::NotLookedUp)
+    pub fn with_resolver(name: impl Into<Cow<'a, str>>, resolver: fn(&str) -> Option<T>) -> Self {
+        CachedProcess {
+            resolver: Some(resolver),
+            ..CachedProcess::new(name)
+        }
     }
-}
 
-/// Trait for accessing a static process local cache.
-pub trait CachedLookup<'a, T> {
-    /// Looks up a process by its name, and caches the result.
-    /// Subsequent calls will used the cached value.
-    fn get(&'a self) -> Option<T>;
+    /// Only caches a failed lookup for the duration of `negative_ttl`.
+    ///
+    /// By default, a process which isn't found is cached as absent until the cache is reset.
+    /// With a negative ttl, the next call to [`CachedLookup::get`] after `negative_ttl` has elapsed
+    /// will lookup the process again, independently of the ttl used for found processes.
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = Some(negative_ttl);
+        self
+    }
 
-    /// Sets the cached lookup. This will prevent any lookups from being made,
-    /// since subsequent calls to [`CachedLookup::get`] will return this cached value.
-    fn set(&'a self, value: T);
+    /// Doubles the negative ttl for each consecutive failed lookup, up to `max_negative_ttl`.
+    ///
+    /// The first failed lookup is cached for the duration set with [`CachedProcess::with_negative_ttl`],
+    /// the next for twice as long, and so on. Finding the process resets the backoff.
+    /// This keeps lookups of a process which is about to be registered responsive,
+    /// without repeatedly looking up a process which is gone.
+    ///
+    /// Has no effect without a negative ttl.
+    pub fn with_max_negative_ttl(mut self, max_negative_ttl: Duration) -> Self {
+        self.max_negative_ttl = Some(max_negative_ttl);
+        self
+    }
 
-    /// Resets the cache, causing the next call to [`CachedLookup::get`] to lookup the process again.
-    fn reset(&'a self);
-}
+    /// Retries a failed lookup up to `retries` times before caching the process as absent.
+    ///
+    /// This is useful when a process may be registered shortly after it's first looked up, such as during startup.
+    /// See [`CachedProcess::with_backoff`] for waiting between attempts.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
 
-impl<T, S> CachedLookup<'static, Process<T, S>> for ProcessLocal<ProcessCached<'_, T, S>> {
-    #[inline]
-    fn get(&'static self) -> Option<Process<T, S>> {
-        self.with(|proc| lookup(proc, |name| Process::lookup(name)))
+    /// Waits for `backoff` between lookup attempts set with [`CachedProcess::with_retries`].
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = Some(backoff);
+        self
     }
 
-    #[inline]
-    fn set(&'static self, value: Process<T, S>) {
-        self.with(|proc| CachedLookup::set(proc, value))
+    /// Looks up the process once more after yielding to the scheduler before caching it as absent.
+    ///
+    /// This avoids caching a process as absent when it's registered just after the first lookup,
+    /// without the delay of [`CachedProcess::with_retries`] and [`CachedProcess::with_backoff`].
+    pub fn with_double_check(mut self) -> Self {
+        self.double_check = true;
+        self
     }
 
-    #[inline]
-    fn reset(&'static self) {
-        self.with(CachedLookup::reset)
+    /// Sets the check used by [`CachedLookup::get_or_reset_if_dead`] to determine if a cached process is still alive.
+    ///
+    /// The lunatic runtime doesn't provide a way to query if a process is alive,
+    /// so it's up to the caller to decide how liveness is tracked (eg. through links or heartbeats).
+    /// Without a liveness check, processes are always considered alive.
+    pub fn with_liveness_check(mut self, is_alive: fn(&T) -> bool) -> Self {
+        self.liveness_check = Some(is_alive);
+        self
     }
-}
 
-impl<T, S> CachedLookup<'static, Process<T, S>> for ProcessCached<'_, T, S> {
-    #[inline]
-    fn get(&'static self) -> Option<Process<T, S>> {
-        lookup(self, |name| Process::lookup(name))
+    /// Sets a hook called with the process name when [`CachedLookup::get_or_reset_if_dead`] finds
+    /// the cached process is no longer alive.
+    ///
+    /// The hook runs after the cache is reset and before the process is looked up again,
+    /// so it can register a replacement process to be found by the lookup.
+    pub fn with_on_dead(mut self, on_dead: fn(&str)) -> Self {
+        self.on_dead = Some(on_dead);
+        self
     }
 
-    #[inline]
-    fn set(&'static self, value: Process<T, S>) {
-        *self.lookup_state.borrow_mut() = LookupState::Present(value);
+    /// Sets a transform applied to the process name and fallback names when looking up the process.
+    ///
+    /// This allows the registry name to be derived from the process name, such as by appending a version,
+    /// while [`CachedProcess::process_name`] still returns the untransformed name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, String> =
+    ///     CachedProcess::new("foo").with_name_transform(|name| format!("{name}@v2"));
+    /// assert_eq!(cache.get_with(|name| Some(name.to_string())), Some("foo@v2".to_string()));
+    /// assert_eq!(cache.process_name(), "foo");
+    /// ```
+    pub fn with_name_transform(mut self, name_transform: fn(&str) -> String) -> Self {
+        self.name_transform = Some(name_transform);
+        self
     }
 
-    #[inline]
-    fn reset(&'static self) {
-        *self.lookup_state.borrow_mut() = LookupState::NotLookedUp;
+    /// Sets a hook called with the process name, and the old and new state when the [`CacheState`] of the cache changes.
+    ///
+    /// This allows cache metrics to be exported to any metrics system.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{CacheState, CachedProcess};
+    ///
+    /// let cache: CachedProcess<'_, usize> =
+    ///     CachedProcess::new("foo").with_on_transition(|name, old, new| {
+    ///         println!("{name} changed from {old:?} to {new:?}");
+    ///     });
+    /// cache.set(1); // Prints "foo changed from NotLookedUp to Present"
+    /// ```
+    pub fn with_on_transition(mut self, on_transition: fn(&str, CacheState, CacheState)) -> Self {
+        self.on_transition = Some(on_transition);
+        self
     }
-}
 
-impl<T> CachedLookup<'static, ProcessRef<T>> for ProcessLocal<ProcessRefCached<'_, T>> {
-    #[inline]
-    fn get(&'static self) -> Option<ProcessRef<T>> {
-        self.with(|proc| lookup(proc, |name| ProcessRef::lookup(name)))
+    /// Marks the cache to be looked up by [`init`] at process startup, rather than on the first lookup.
+    ///
+    /// This bounds the latency of the first request which uses the cache.
+    pub fn with_prewarm(mut self) -> Self {
+        self.prewarm = true;
+        self
     }
 
-    #[inline]
-    fn set(&'static self, value: ProcessRef<T>) {
-        self.with(|proc| CachedLookup::set(proc, value))
+    /// Returns true if the cache is looked up by [`init`], set with [`CachedProcess::with_prewarm`].
+    pub fn is_prewarm(&self) -> bool {
+        self.prewarm
     }
 
-    #[inline]
-    fn reset(&'static self) {
-        self.with(CachedLookup::reset)
+    /// Sets the clock used to expire lookups, instead of the [`SystemClock`].
+    ///
+    /// This is mainly useful for testing ttls deterministically with a [`ManualClock`].
+    pub fn with_clock(mut self, clock: &'a dyn Clock) -> Self {
+        self.clock = Some(clock);
+        self
     }
-}
 
-impl<T> CachedLookup<'static, ProcessRef<T>> for ProcessRefCached<'_, T> {
-    #[inline]
-    fn get(&'static self) -> Option<ProcessRef<T>> {
-        lookup(self, |name| ProcessRef::lookup(name))
+    /// Construct a new process cache without a process name, which must be set with [`CachedProcess::set_name`].
+    ///
+    /// This is useful when the name is only known at runtime, such as for plugins.
+    /// Until a name is set, lookups return [`LookupError::Unnamed`] and the process name is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::unnamed();
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), None);
+    ///
+    /// assert!(cache.set_name("foo"));
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), Some(3));
+    /// ```
+    pub fn unnamed() -> Self {
+        CachedProcess {
+            process_name: ProcessName::new(None),
+            ..CachedProcess::new_const("")
+        }
     }
 
-    #[inline]
-    fn set(&'static self, value: ProcessRef<T>) {
-        *self.lookup_state.borrow_mut() = LookupState::Present(value);
+    /// Returns the process name.
+    pub fn process_name(&self) -> &str {
+        self.process_name.get().unwrap_or_default()
     }
 
-    #[inline]
-    fn reset(&'static self) {
-        *self.lookup_state.borrow_mut() = LookupState::NotLookedUp;
+    /// Sets the process name of a cache constructed with [`CachedProcess::unnamed`], returning whether it was set.
+    ///
+    /// A name can only be set once, and never replaces the name of a named cache.
+    /// The name should be set before the cache is used as a [`ByName`] key, as it changes its hash.
+    pub fn set_name(&self, name: impl Into<String>) -> bool {
+        self.process_name.set(name.into())
     }
-}
 
-/// Macro for defining a process local lookup cache for processes.
-///
-/// The structure is as follows:
-///
-/// ```
-/// static <ident>: <process_type> = <process_name>;
-/// ```
-///
-/// Where
-///
-/// - `<ident>`: Static variable name.
-/// - `<process_type>`: Either `Process<T>`, `ProcessRef<T>`, or `Process<T, S>` where `T` is the message type, and `S` is the serializer.
-/// - `<process_name>`: The string literal of the process name.
-///
-/// # Examples
-///
-/// Cached [`lunatic::Process`].
-///
-/// ```
-/// use lunatic_cached_process::cached_process;
-/// use serde::{Serialize, Deserialize};
-///
-/// cached_process! {
-///     static COUNTER: Process<CountMessage> = "global-counter-process";
-/// }
-///
-/// #[derive(Serialize, Deserialize)]
-/// enum CountMessage {
-///     Inc,
-///     Dec,
-/// }
-/// ```
-///
-/// Cached [`lunatic::process::ProcessRef`].
-///
-/// ```
-/// use lunatic_cached_process::cached_process;
-///
-/// cached_process! {
-///     static COUNTER: ProcessRef<CounterProcess> = "global-counter-process-ref";
-/// }
-///
-/// struct CounterProcess;
-///
-/// impl lunatic::process::AbstractProcess for CounterProcess {
-///     type Arg = ();
-///     type State = Self;
-/// }
-/// ```
-#[macro_export]
-macro_rules! cached_process {
-    (
-        $(
-            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> = $name:tt ;
-        )+
-    ) => {
-        $crate::paste! {
-            $(
-                lunatic::process_local! {
-                    $(#[$attr])* $vis static $ident: $crate:: [<$process_type Cached>] <'static, $ty $( , $s )?> = $crate::CachedProcess::new($name);
-                }
-            )+
+    /// Returns true if the cache was constructed with [`CachedProcess::unnamed`] and its name hasn't been set yet.
+    pub fn is_unnamed(&self) -> bool {
+        self.process_name.get().is_none()
+    }
+
+    /// Returns true if both caches have the same process name, regardless of their cached state.
+    ///
+    /// Unlike `==`, this treats caches of the same process as equivalent even if only one has been looked up.
+    pub fn refers_to_same(&self, other: &Self) -> bool {
+        self.process_name == other.process_name
+    }
+
+    /// Returns the name which the cached process was found under.
+    ///
+    /// This is only `Some` if the process was looked up and found, and is useful when using [`CachedProcess::new_with_fallbacks`].
+    pub fn resolved_name(&self) -> Option<&str> {
+        match &*self.lookup_state.borrow() {
+            LookupState::Present(_, _, Some(name_index)) => self.names().nth(*name_index),
+            _ => None,
         }
-    };
-}
+    }
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
-enum LookupState<T> {
-    NotLookedUp,
-    NotPresent,
-    Present(T),
-}
+    /// Returns true if the process has been looked up and exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::spawn_link;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert!(!FOO.with(|process| process.is_present())); // Initially not present
+    ///
+    /// FOO.get();
+    /// assert!(!FOO.with(|process| process.is_present())); // Not present, even after lookup
+    ///
+    /// // Start a process called "foo"
+    /// spawn_link!(|mailbox: Mailbox<()>| { loop { mailbox.receive(); } }).register("foo");
+    ///
+    /// FOO.reset();
+    /// FOO.get();
+    /// assert!(FOO.with(|process| process.is_present())); // Is present
+    /// ```
+    pub fn is_present(&self) -> bool {
+        matches!(&*self.lookup_state.borrow(), LookupState::Present(..))
+    }
 
-impl<T> Default for LookupState<T> {
-    fn default() -> Self {
-        LookupState::NotLookedUp
+    /// Returns true if the process has been looked up, regardless if the process was found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert!(!FOO.with(|process| process.is_looked_up()));
+    ///
+    /// FOO.get();
+    /// assert!(FOO.with(|process| process.is_looked_up())); // Looked up, even though "foo" isn't registered
+    /// ```
+    pub fn is_looked_up(&self) -> bool {
+        !matches!(&*self.lookup_state.borrow(), LookupState::NotLookedUp)
     }
-}
 
-#[inline]
-fn lookup<'a, F, T>(proc: &'a CachedProcess<T>, f: F) -> Option<T>
-where
-    F: Fn(&'a str) -> Option<T>,
-    T: Clone,
-{
-    let proc_ref = proc.lookup_state.borrow();
-    match &*proc_ref {
-        LookupState::NotLookedUp => {
-            std::mem::drop(proc_ref);
-            match f(proc.process_name) {
-                Some(process) => {
-                    *proc.lookup_state.borrow_mut() = LookupState::Present(process.clone()); // TODO: Replace clone with copy
-                    Some(process)
-                }
-                None => {
-                    *proc.lookup_state.borrow_mut() = LookupState::NotPresent;
-                    None
-                }
+    /// Returns true if the process has been looked up and does not exist.
+    ///
+    /// Unlike [`CachedProcess::is_looked_up`], this distinguishes a failed lookup from no lookup at all.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert!(!FOO.with(|process| process.is_not_present())); // Not looked up yet
+    ///
+    /// FOO.get();
+    /// assert!(FOO.with(|process| process.is_not_present())); // Looked up, and "foo" isn't registered
+    /// ```
+    pub fn is_not_present(&self) -> bool {
+        matches!(&*self.lookup_state.borrow(), LookupState::NotPresent(..))
+    }
+
+    /// Looks up a value by its name with a custom `resolver`, and caches the result.
+    ///
+    /// This allows caching any name resolved resource, not just processes.
+    /// The resolver is only called when there is no cached value, and is called with each name in lookup order
+    /// until it returns `Some`. The cache can be set and cleared with [`CachedProcess::set`] and [`CachedProcess::reset`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), Some(3));
+    /// assert_eq!(cache.get_with(|_| None), Some(3)); // Uses the cached value
+    /// ```
+    pub fn get_with<F>(&self, resolver: F) -> Option<T>
+    where
+        F: Fn(&str) -> Option<T>,
+        T: Clone,
+    {
+        lookup(self, resolver).ok()
+    }
+
+    /// Looks up the process like [`CachedLookup::get`], calling `f` with a reference to the cached process.
+    ///
+    /// Unlike [`CachedLookup::get`], the cached process isn't cloned when it's served from the cache.
+    /// The cache is borrowed while `f` runs, so `f` must not modify the cache, otherwise it panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedProcess, ProcessCached};
+    ///
+    /// let cache: ProcessCached<'_, ()> = CachedProcess::new("foo");
+    /// cache.set(Process::this());
+    /// assert!(cache.with(|process| process.is_some_and(|process| process == &Process::this())));
+    /// ```
+    pub fn with<R, F>(&'a self, f: F) -> R
+    where
+        Self: CachedLookup<'a, T>,
+        F: FnOnce(Option<&T>) -> R,
+    {
+        if cfg!(feature = "no-cache") {
+            let found = self.try_get().ok();
+            return f(found.as_ref());
+        }
+
+        let is_cached = match &*self.lookup_state.borrow() {
+            LookupState::Present(_, resolved_at, _) => {
+                !is_expired(self.ttl, resolved_at, self.now())
             }
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => false,
+        };
+        if is_cached {
+            self.record_hit(true);
+        } else {
+            let _ = self.try_get();
         }
-        LookupState::NotPresent => None,
-        LookupState::Present(process) => {
-            Some(process.clone()) // TODO: Replace clone with copy
+
+        match &*self.lookup_state.borrow() {
+            LookupState::Present(process, _, _) => f(Some(process)),
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => f(None),
         }
     }
+
+    /// Looks up the process like [`CachedLookup::get`], along with metadata fetched from it by `fetch`.
+    ///
+    /// The metadata is fetched when the process is first resolved, and cached until the cached process changes,
+    /// such as when it's reset or its ttl expires. Fetching a different type of metadata replaces the cached metadata.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedProcess, ProcessCached};
+    ///
+    /// let cache: ProcessCached<'_, ()> = CachedProcess::new("foo");
+    /// cache.set(Process::this());
+    /// assert_eq!(cache.get_with_meta(|_| "v1"), Some((Process::this(), "v1")));
+    /// assert_eq!(cache.get_with_meta(|_| "v2"), Some((Process::this(), "v1"))); // Uses the cached metadata
+    /// ```
+    pub fn get_with_meta<M, F>(&'a self, fetch: F) -> Option<(T, M)>
+    where
+        Self: CachedLookup<'a, T>,
+        M: Clone + Send + 'static,
+        F: FnOnce(&T) -> M,
+    {
+        let process = self.get()?;
+        if cfg!(feature = "no-cache") {
+            let meta = fetch(&process);
+            return Some((process, meta));
+        }
+
+        let cached = self
+            .meta
+            .borrow()
+            .as_ref()
+            .and_then(|meta| meta.value.downcast_ref::<M>().cloned());
+        let meta = match cached {
+            Some(meta) => meta,
+            None => {
+                let meta = fetch(&process);
+                *self.meta.borrow_mut() = Some(Meta::new(meta.clone()));
+                meta
+            }
+        };
+        Some((process, meta))
+    }
+
+    /// Sets the cached value, preventing any lookups from being made.
+    ///
+    /// The value is still validated by the liveness check when it's looked up with
+    /// [`CachedLookup::get_or_reset_if_dead`]. See [`CachedLookup::set`].
+    /// With the `no-cache` feature the value is still stored, but lookups ignore it.
+    pub fn set(&self, value: T) {
+        self.set_state(LookupState::Present(value, self.now(), None));
+    }
+
+    /// Sets the cached value like [`CachedProcess::set`], but only if it passes the liveness check
+    /// set with [`CachedProcess::with_liveness_check`]. Returns `true` if the value was stored.
+    ///
+    /// This allows injecting a process while keeping the cache self-healing, as a dead process is never cached,
+    /// and a process which dies later is invalidated by [`CachedLookup::get_or_reset_if_dead`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> =
+    ///     CachedProcess::new("foo").with_liveness_check(|value| *value != 0);
+    /// assert!(!cache.set_validated(0));
+    /// assert!(!cache.is_looked_up());
+    /// assert!(cache.set_validated(1));
+    /// ```
+    pub fn set_validated(&self, value: T) -> bool {
+        let is_alive = self.is_alive(&value);
+        if is_alive {
+            self.set(value);
+        }
+        is_alive
+    }
+
+    /// Sets the cached value like [`CachedProcess::set`], returning the previously cached value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// assert_eq!(cache.replace(1), None);
+    /// assert_eq!(cache.replace(2), Some(1));
+    /// ```
+    pub fn replace(&self, value: T) -> Option<T> {
+        match self.set_state(LookupState::Present(value, self.now(), None)) {
+            LookupState::Present(process, _, _) => Some(process),
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => None,
+        }
+    }
+
+    /// Sets the cached value only if the cache doesn't already hold a value, returning whether it was stored.
+    ///
+    /// See [`CachedLookup::set_if_absent`].
+    pub fn set_if_absent(&self, value: T) -> bool {
+        let is_absent = !matches!(&*self.lookup_state.borrow(), LookupState::Present(..));
+        if is_absent {
+            self.set_state(LookupState::Present(value, self.now(), None));
+        }
+        is_absent
+    }
+
+    /// Resets the cache, causing the next call to lookup the value again.
+    ///
+    /// This also clears the backoff of consecutive failed lookups. See [`CachedProcess::reset_keep_backoff`]
+    /// for resetting the cache from a periodic retry loop, and [`CachedLookup::reset`].
+    pub fn reset(&self) {
+        self.take();
+    }
+
+    /// Resets the cache like [`CachedProcess::reset`], returning the previously cached value.
+    ///
+    /// The cached value is returned even if its ttl has expired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// cache.set(1);
+    /// assert_eq!(cache.take(), Some(1));
+    /// assert_eq!(cache.take(), None);
+    /// assert!(!cache.is_looked_up());
+    /// ```
+    pub fn take(&self) -> Option<T> {
+        let previous = self.set_state(self.reset_state());
+        match previous {
+            LookupState::Present(process, _, _) => Some(process),
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => None,
+        }
+    }
+
+    /// Consumes the cache, returning its process name and the cached process, if any.
+    ///
+    /// An expired process isn't returned, and a process linked with [`CachedLookup::get_linked`] is unlinked.
+    /// The name of an unnamed cache is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// cache.set(1);
+    /// let (name, process) = cache.into_parts();
+    /// assert_eq!(name, "foo");
+    /// assert_eq!(process, Some(1));
+    /// ```
+    pub fn into_parts(self) -> (Cow<'a, str>, Option<T>) {
+        self.unlink();
+        let now = self.now();
+        let process = match self.lookup_state.into_inner() {
+            LookupState::Present(process, resolved_at, _)
+                if !is_expired(self.ttl, &resolved_at, now) =>
+            {
+                Some(process)
+            }
+            _ => None,
+        };
+        let name = match self.process_name.declared {
+            Some(name) => name,
+            None => Cow::Owned(self.process_name.assigned.into_inner().unwrap_or_default()),
+        };
+        (name, process)
+    }
+
+    /// Disables the cache, caching the process as absent and preventing any lookups from being made.
+    ///
+    /// Lookups return [`LookupError::Disabled`] until the cache is enabled with [`CachedProcess::enable`],
+    /// even after the cache is reset. This is useful for optional dependencies which are turned off by configuration.
+    /// A process can still be cached explicitly with [`CachedProcess::set`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// cache.disable();
+    /// cache.reset();
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), None);
+    ///
+    /// cache.enable();
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), Some(3));
+    /// ```
+    pub fn disable(&self) {
+        self.disabled.set(true);
+        self.reset();
+    }
+
+    /// Enables a cache disabled with [`CachedProcess::disable`], so the next call looks up the process again.
+    pub fn enable(&self) {
+        self.disabled.set(false);
+        self.reset();
+    }
+
+    /// Returns true if the cache was disabled with [`CachedProcess::disable`].
+    pub fn is_disabled(&self) -> bool {
+        self.disabled.get()
+    }
+
+    /// Wraps the cache in a [`ByName`], which is hashed and compared only by its process name.
+    pub fn by_name(self) -> ByName<'a, T> {
+        ByName(self)
+    }
+
+    /// Returns a copy of the cache with the same name and configuration, which hasn't been looked up.
+    ///
+    /// Unlike the derived [`Clone`], which copies the cached process, stats and link,
+    /// the returned cache performs its own lookup. Use this when the clone is used in a context
+    /// where the cached process may not be valid, such as in another process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::CachedProcess;
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// cache.set(1);
+    /// assert_eq!(cache.clone().get_with(|_| None), Some(1));
+    /// assert_eq!(cache.fresh_clone().get_with(|_| None), None);
+    /// ```
+    pub fn fresh_clone(&self) -> Self {
+        CachedProcess {
+            process_name: self.process_name.clone(),
+            fallback_names: self.fallback_names,
+            ttl: self.ttl,
+            negative_ttl: self.negative_ttl,
+            max_negative_ttl: self.max_negative_ttl,
+            retries: self.retries,
+            backoff: self.backoff,
+            double_check: self.double_check,
+            node_id: self.node_id,
+            resolution_order: self.resolution_order,
+            liveness_check: self.liveness_check,
+            on_dead: self.on_dead,
+            resolver: self.resolver,
+            name_transform: self.name_transform,
+            on_transition: self.on_transition,
+            prewarm: self.prewarm,
+            clock: self.clock,
+            disabled: self.disabled.clone(),
+            ..CachedProcess::new_const("")
+        }
+    }
+
+    /// Resets the cache only if the process was looked up and not found, keeping a cached process.
+    ///
+    /// This is useful for periodically retrying processes which haven't been found yet.
+    pub fn reset_if_absent(&self) {
+        if self.state() == CacheState::Absent {
+            self.set_state(self.reset_state());
+        }
+    }
+
+    /// Resets the cache like [`CachedProcess::reset`], but keeps the backoff of a failed lookup
+    /// set with [`CachedProcess::with_negative_ttl`].
+    ///
+    /// A failed lookup is only looked up again once its negative ttl has expired, and the attempts counted
+    /// by [`CachedProcess::with_max_negative_ttl`] are kept. This should be used by a periodic retry loop,
+    /// as calling [`CachedProcess::reset`] on every iteration would look up the process every time.
+    /// Without a negative ttl there is no backoff to keep, so a failed lookup is reset.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use lunatic_cached_process::{CacheState, CachedProcess};
+    ///
+    /// let cache: CachedProcess<'_, usize> =
+    ///     CachedProcess::new("foo").with_negative_ttl(Duration::from_secs(60));
+    /// assert_eq!(cache.get_with(|_| None), None);
+    ///
+    /// cache.reset_keep_backoff();
+    /// assert_eq!(cache.state(), CacheState::Absent);
+    /// assert_eq!(cache.get_with(|name| Some(name.len())), None); // Still backing off
+    /// ```
+    pub fn reset_keep_backoff(&self) {
+        if self.negative_ttl.is_none() || self.state() != CacheState::Absent {
+            self.reset();
+        }
+    }
+
+    /// Resets the cache only if it holds a cached process, keeping a failed lookup.
+    pub fn reset_if_present(&self) {
+        if self.state() == CacheState::Present {
+            self.set_state(self.reset_state());
+        }
+    }
+
+    /// Resets the cache if the cached process was linked with [`CachedLookup::get_linked`] using `tag`,
+    /// returning whether the cache was reset.
+    ///
+    /// This should be called with the tag of a [`lunatic::MailboxResult::LinkDied`] message.
+    /// See [`reset_linked`] for caches declared with the [`cached_process!`] macro.
+    pub fn reset_if_linked(&self, tag: Tag) -> bool {
+        match self.link.get() {
+            Some((link_tag, _)) if link_tag == tag => {
+                self.link.set(None);
+                self.reset();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Links the process with `process_id` to the current process, unless it's already linked.
+    fn link(&self, process_id: u64) {
+        if self
+            .link
+            .get()
+            .is_some_and(|(_, linked_id)| linked_id == process_id)
+        {
+            return;
+        }
+
+        self.unlink();
+        let tag = Tag::new();
+        unsafe { lunatic::host::api::process::link(tag.id(), process_id) };
+        self.link.set(Some((tag, process_id)));
+    }
+
+    /// Unlinks the process linked with [`CachedProcess::link`], if any.
+    fn unlink(&self) {
+        if let Some((_, process_id)) = self.link.take() {
+            unsafe { lunatic::host::api::process::unlink(process_id) };
+        }
+    }
+
+    /// Returns the state of the cache, without the cached process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CacheState, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert_eq!(FOO.with(|process| process.state()), CacheState::NotLookedUp);
+    ///
+    /// FOO.get();
+    /// assert_eq!(FOO.with(|process| process.state()), CacheState::Absent);
+    /// ```
+    pub fn state(&self) -> CacheState {
+        self.lookup_state.borrow().cache_state()
+    }
+
+    /// Returns the result of the last lookup, without looking up the process.
+    ///
+    /// Unlike [`CachedLookup::get`], this distinguishes a lookup which didn't find the process
+    /// from a process which hasn't been looked up yet. A cached process whose ttl has expired
+    /// is [`Resolution::NotResolved`], as the next lookup will resolve it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup, Resolution};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert_eq!(FOO.with(|process| process.resolve()), Resolution::NotResolved);
+    ///
+    /// FOO.get();
+    /// match FOO.with(|process| process.resolve()) {
+    ///     Resolution::NotResolved => unreachable!(),
+    ///     Resolution::Absent => println!("foo isn't registered"),
+    ///     Resolution::Present(process) => println!("foo is {process:?}"),
+    /// }
+    /// ```
+    pub fn resolve(&self) -> Resolution<T>
+    where
+        T: Clone,
+    {
+        match &*self.lookup_state.borrow() {
+            LookupState::NotLookedUp => Resolution::NotResolved,
+            LookupState::NotPresent(..) => Resolution::Absent,
+            LookupState::Present(process, resolved_at, _) => {
+                if is_expired(self.ttl, resolved_at, self.now()) {
+                    Resolution::NotResolved
+                } else {
+                    Resolution::Present(process.duplicate())
+                }
+            }
+        }
+    }
+
+    /// Returns true if the cache holds `process`.
+    ///
+    /// This doesn't look up the process, and ignores the ttl of the cached process.
+    pub fn holds(&self, process: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        matches!(&*self.lookup_state.borrow(), LookupState::Present(cached, _, _) if cached == process)
+    }
+
+    /// Returns true if the cached process is older than the ttl set with [`CachedProcess::with_ttl`],
+    /// without looking up the process.
+    ///
+    /// This allows callers to decide when to call [`CachedLookup::refresh`], rather than having the next lookup
+    /// look up the process again. Returns `false` if no process is cached, or if the cache has no ttl.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use lunatic_cached_process::{CachedProcess, ManualClock};
+    ///
+    /// let clock = ManualClock::new();
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::builder("foo")
+    ///     .ttl(Duration::from_secs(10))
+    ///     .clock(&clock)
+    ///     .build();
+    /// cache.set(1);
+    /// assert!(!cache.is_stale());
+    ///
+    /// clock.advance(Duration::from_secs(10));
+    /// assert!(cache.is_stale());
+    /// ```
+    pub fn is_stale(&self) -> bool {
+        match &*self.lookup_state.borrow() {
+            LookupState::Present(_, resolved_at, _) => {
+                is_expired(self.ttl, resolved_at, self.now())
+            }
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => false,
+        }
+    }
+
+    /// Returns how long ago the cached process was resolved or set, or `None` if no process is cached.
+    ///
+    /// This doesn't expire the cached process, but allows callers to implement their own staleness policies.
+    pub fn resolved_age(&self) -> Option<Duration> {
+        match &*self.lookup_state.borrow() {
+            LookupState::Present(_, resolved_at, _) => {
+                Some(self.now().saturating_duration_since(*resolved_at))
+            }
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => None,
+        }
+    }
+
+    /// Returns the process name and the cached process, without looking up the process.
+    ///
+    /// This is useful for reporting the state of caches, such as in a status dashboard.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// FOO.get();
+    /// assert_eq!(FOO.with(|process| process.snapshot()), ("foo".to_string(), None));
+    /// ```
+    pub fn snapshot(&self) -> (String, Option<T>)
+    where
+        T: Clone,
+    {
+        (self.process_name().to_string(), peek(self))
+    }
+
+    /// Exports the process name and state of the cache, without the cached process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{CacheState, CachedProcess};
+    ///
+    /// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// cache.get_with(|_| None);
+    ///
+    /// let restored: CachedProcess<'_, usize> = CachedProcess::new("foo");
+    /// assert!(restored.import(cache.export()));
+    /// assert_eq!(restored.state(), CacheState::Absent);
+    /// ```
+    pub fn export(&self) -> CacheSnapshot {
+        CacheSnapshot {
+            name: self.process_name().to_string(),
+            state: self.state(),
+        }
+    }
+
+    /// Imports a snapshot from [`CachedProcess::export`], returning whether it was imported.
+    ///
+    /// The snapshot is only imported if its name matches the process name, or if the cache is unnamed,
+    /// in which case the name is set. A process which was absent is cached as absent, while a process
+    /// which was present is looked up again on the next call, as the snapshot doesn't hold the process.
+    pub fn import(&self, snapshot: CacheSnapshot) -> bool {
+        if self.is_unnamed() {
+            self.set_name(snapshot.name);
+        } else if self.process_name() != snapshot.name {
+            return false;
+        }
+
+        self.set_state(match snapshot.state {
+            CacheState::Absent => LookupState::NotPresent(self.now(), 0),
+            CacheState::NotLookedUp | CacheState::Present => LookupState::NotLookedUp,
+        });
+        true
+    }
+
+    /// Returns the process name followed by any fallback names, in lookup order.
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.process_name()).chain(self.fallback_names.iter().copied())
+    }
+
+    /// Returns the names the process is registered under, with the name transform applied, in lookup order.
+    fn registry_names(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.names().map(|name| self.registry_name(name))
+    }
+
+    /// Returns the name `name` is registered under, with the name transform applied.
+    fn registry_name<'n>(&self, name: &'n str) -> Cow<'n, str> {
+        match self.name_transform {
+            Some(name_transform) => Cow::Owned(name_transform(name)),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    /// Returns the cache hit and miss counters for calls to [`CachedLookup::get`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// FOO.get(); // Miss, "foo" isn't registered
+    /// FOO.get(); // Hit, using the cached lookup
+    ///
+    /// let stats = FOO.with(|process| process.stats());
+    /// assert_eq!(stats.hits, 1);
+    /// assert_eq!(stats.misses, 1);
+    /// assert_eq!(stats.absent, 1);
+    /// ```
+    pub fn stats(&self) -> CacheStats {
+        self.stats.get()
+    }
+
+    /// Zeroes the stats of the cache without resetting the cached lookup.
+    ///
+    /// This is useful for reporting stats per interval, by reading [`CachedProcess::stats`] and then clearing them.
+    pub fn clear_stats(&self) {
+        self.stats.set(CacheStats::default());
+    }
+
+    /// Returns whether the most recent lookup used the cached state, or `None` if the cache hasn't been used yet.
+    ///
+    /// Unlike [`CachedProcess::stats`], this only describes the latest call to [`CachedLookup::get`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// assert_eq!(FOO.with(|process| process.last_was_hit()), None);
+    /// FOO.get();
+    /// assert_eq!(FOO.with(|process| process.last_was_hit()), Some(false));
+    /// FOO.get();
+    /// assert_eq!(FOO.with(|process| process.last_was_hit()), Some(true));
+    /// ```
+    pub fn last_was_hit(&self) -> Option<bool> {
+        self.last_was_hit.get()
+    }
+
+    fn record_stats(&self, f: impl FnOnce(&mut CacheStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+
+    /// Returns the custom resolver set with [`CachedProcess::with_resolver`], or `default` if there isn't one.
+    fn resolver(&self, default: fn(&str) -> Option<T>) -> fn(&str) -> Option<T> {
+        self.resolver.unwrap_or(default)
+    }
+
+    /// Returns true if the process passes the liveness check, or if there is no liveness check.
+    fn is_alive(&self, process: &T) -> bool {
+        self.liveness_check.is_none_or(|is_alive| is_alive(process))
+    }
+
+    /// Returns the state of a reset cache, which stays absent while the cache is disabled.
+    fn reset_state(&self) -> LookupState<T> {
+        if self.disabled.get() {
+            LookupState::NotPresent(self.now(), 0)
+        } else {
+            LookupState::NotLookedUp
+        }
+    }
+
+    /// Replaces the lookup state, calling the transition hook if the [`CacheState`] changed.
+    ///
+    /// A process linked with [`CachedLookup::get_linked`] is unlinked when the cached process is replaced or removed,
+    /// so a stale link can't reset the cache. [`CachedLookup::get_linked`] links the new process again.
+    fn set_state(&self, state: LookupState<T>) -> LookupState<T> {
+        let new = state.cache_state();
+        let previous = std::mem::replace(
+            &mut *self
+                .lookup_state
+                .try_borrow_mut()
+                .expect("process cache modified while borrowed by `CachedProcess::with`"),
+            state,
+        );
+        *self.meta.borrow_mut() = None;
+        if let LookupState::Present(..) = previous {
+            self.unlink();
+        }
+        let old = previous.cache_state();
+        if let Some(on_transition) = self.on_transition {
+            if old != new {
+                on_transition(self.process_name(), old, new);
+            }
+        }
+        previous
+    }
+
+    /// Records a lookup which was served from the cache.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn record_hit(&self, present: bool) {
+        self.record_stats(|stats| stats.hits += 1);
+        self.last_was_hit.set(Some(true));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            process_name = self.process_name(),
+            present,
+            "process cache hit"
+        );
+    }
+
+    /// Returns the current time of the clock set with [`CachedProcess::with_clock`].
+    fn now(&self) -> Instant {
+        self.clock.map_or_else(Instant::now, |clock| clock.now())
+    }
+
+    /// Returns the negative ttl of a failed lookup after `attempts` consecutive failed lookups.
+    fn negative_ttl_after(&self, attempts: u32) -> Option<Duration> {
+        let negative_ttl = self.negative_ttl?;
+        match self.max_negative_ttl {
+            Some(max_negative_ttl) => Some(
+                negative_ttl
+                    .saturating_mul(2u32.saturating_pow(attempts))
+                    .min(max_negative_ttl),
+            ),
+            None => Some(negative_ttl),
+        }
+    }
+}
+
+impl<'a, T, S> ProcessCached<'a, T, S> {
+    /// Construct a new process cache for a process registered on a specific node.
+    ///
+    /// A process registered under the name on any other node is treated as not found,
+    /// so caches of the same name on different nodes are cached and reset independently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::distributed::node_id;
+    /// use lunatic_cached_process::{CachedLookup, ProcessCached};
+    ///
+    /// let local: ProcessCached<'_, ()> = ProcessCached::on_node(node_id(), "foo");
+    /// let remote: ProcessCached<'_, ()> = ProcessCached::on_node(node_id() + 1, "foo");
+    /// assert!(local.get().is_none());
+    /// assert!(remote.get().is_none());
+    /// ```
+    pub fn on_node(node_id: u64, name: impl Into<Cow<'a, str>>) -> Self {
+        CachedProcess {
+            node_id: Some(node_id),
+            ..CachedProcess::new(name)
+        }
+    }
+
+    /// Construct a new process cache for the process with `id` on the current node, instead of a registered name.
+    ///
+    /// The process name is the id, and the handle is constructed from the id rather than looked up in the registry.
+    /// lunatic can't check whether a process exists, so the handle is returned even if the process has died.
+    /// A liveness check can be set with [`CachedProcess::with_liveness_check`] to validate it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedLookup, ProcessCached};
+    ///
+    /// let cache: ProcessCached<'_, ()> = ProcessCached::by_id(Process::<()>::this().id());
+    /// assert_eq!(cache.get(), Some(Process::this()));
+    /// ```
+    pub fn by_id(id: u64) -> Self {
+        CachedProcess::with_resolver(id.to_string(), |name| {
+            let id: u64 = name.parse().ok()?;
+            convert_handle(&(lunatic::distributed::node_id(), id))
+        })
+    }
+
+    /// Sets the sources a found process is accepted from, in priority order.
+    ///
+    /// The lunatic registry holds a single registration per name, so each name is still looked up once,
+    /// and the process is accepted by the first source matching the node it's registered on.
+    /// The accepting source is cached along with the process, and can be retrieved with [`ProcessCached::resolved_source`].
+    /// A process from any node is accepted if the order is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{CachedLookup, ProcessCached, ResolutionSource};
+    ///
+    /// let cache: ProcessCached<'_, ()> = ProcessCached::new("foo")
+    ///     .with_resolution_order(&[ResolutionSource::Local, ResolutionSource::Node(1)]);
+    /// assert!(cache.get().is_none());
+    /// assert_eq!(cache.resolved_source(), None);
+    /// ```
+    pub fn with_resolution_order(mut self, resolution_order: &'a [ResolutionSource]) -> Self {
+        self.resolution_order = resolution_order;
+        self
+    }
+
+    /// Looks up the process like [`CachedLookup::get`], along with the id of the node it's running on.
+    ///
+    /// The node id is part of the cached process handle, so it's cached along with the process.
+    /// A process found on the current node returns [`lunatic::distributed::node_id`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::{distributed::node_id, Process};
+    /// use lunatic_cached_process::{CachedProcess, ProcessCached};
+    ///
+    /// let cache: ProcessCached<'_, ()> = CachedProcess::new("foo");
+    /// assert_eq!(cache.get_with_node(), None);
+    /// cache.set(Process::this());
+    /// assert_eq!(cache.get_with_node(), Some((Process::this(), node_id())));
+    /// ```
+    pub fn get_with_node(&self) -> Option<(Process<T, S>, u64)> {
+        let process = self.get()?;
+        let node_id = process.node_id();
+        Some((process, node_id))
+    }
+
+    /// Returns the source which accepted the cached process, set with [`ProcessCached::with_resolution_order`].
+    ///
+    /// This is only `Some` if the process was looked up and found.
+    pub fn resolved_source(&self) -> Option<ResolutionSource> {
+        self.resolved_name().and(self.resolved_source.get())
+    }
+
+    /// Returns the resolver of the cache, ignoring processes not registered on the node set with [`ProcessCached::on_node`],
+    /// or not accepted by any source of the resolution order.
+    fn process_resolver(&self) -> impl Fn(&str) -> Option<Process<T, S>> + '_ {
+        let resolver = self.resolver(Process::lookup);
+        move |name| resolver(name).filter(|process| self.accepts(process))
+    }
+
+    /// Returns true if the process is registered on the node set with [`ProcessCached::on_node`],
+    /// and is accepted by a source of the resolution order, recording the source which accepted it.
+    fn accepts(&self, process: &Process<T, S>) -> bool {
+        if self
+            .node_id
+            .is_some_and(|node_id| process.node_id() != node_id)
+        {
+            return false;
+        }
+        if self.resolution_order.is_empty() {
+            return true;
+        }
+        let source = self
+            .resolution_order
+            .iter()
+            .find(|source| source.accepts(process.node_id()));
+        self.resolved_source.set(source.copied());
+        source.is_some()
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], spawning and registering it with `spawn` if it's not found.
+    ///
+    /// If another process registers under the same name while spawning, the existing registration is preferred
+    /// and the process returned by `spawn` is left running unregistered.
+    /// A disabled cache returns `None` without spawning a process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::spawn_link;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// let process = FOO.with(|process| {
+    ///     process.get_or_spawn(|| spawn_link!(|mailbox: Mailbox<()>| { loop { mailbox.receive(); } }))
+    /// });
+    /// assert_eq!(FOO.get(), process);
+    /// ```
+    pub fn get_or_spawn<F>(&self, spawn: F) -> Option<Process<T, S>>
+    where
+        F: FnOnce() -> Process<T, S>,
+    {
+        if self.disabled.get() {
+            return None;
+        }
+        if let Some(process) = self.get() {
+            return Some(process);
+        }
+
+        // The process may have been registered since it was cached as not present
+        self.reset();
+        if let Some(process) = self.get() {
+            return Some(process);
+        }
+
+        let process = spawn();
+        process.register(&self.registry_name(self.process_name()));
+
+        // Registering overwrites existing registrations, so lookup again in case of a concurrent registration
+        self.reset();
+        match self.get() {
+            Some(registered) => Some(registered),
+            None => {
+                self.set(process);
+                Some(process)
+            }
+        }
+    }
+}
+
+impl<T> ProcessRefCached<'_, T> {
+    /// Looks up the process like [`CachedLookup::get`], returning it as a plain [`Process`] handle.
+    ///
+    /// This allows sending raw messages to an abstract process, bypassing its handlers.
+    /// lunatic doesn't provide a conversion between the handles, so the process is converted
+    /// through their shared serialized representation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: ProcessRef<()> = "foo";
+    /// }
+    ///
+    /// let process: Option<Process<()>> = FOO.with(|process| process.as_process());
+    /// assert!(process.is_none());
+    /// ```
+    pub fn as_process<M, S>(&self) -> Option<Process<M, S>> {
+        let process = self.get()?;
+        convert_handle(&process)
+    }
+
+    /// Looks up the process like [`CachedLookup::get`], along with the id of the node it's running on.
+    ///
+    /// See [`ProcessCached::get_with_node`].
+    pub fn get_with_node(&self) -> Option<(ProcessRef<T>, u64)> {
+        let process = self.get()?;
+        let (node_id, _): (u64, u64) = convert_handle(&process)?;
+        Some((process, node_id))
+    }
+}
+
+/// Caches are equal if their state and configuration are equal, ignoring the liveness check, resolver, clock and stats.
+impl<'a, T: PartialEq> PartialEq for CachedProcess<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        let (state, other_state) = (self.lookup_state.borrow(), other.lookup_state.borrow());
+        // When each lookup was made isn't compared, so caches holding the same process are equal
+        state.cache_state() == other_state.cache_state()
+            && state.process() == other_state.process()
+            && self.process_name == other.process_name
+            && self.fallback_names == other.fallback_names
+            && self.ttl == other.ttl
+            && self.negative_ttl == other.negative_ttl
+            && self.max_negative_ttl == other.max_negative_ttl
+            && self.retries == other.retries
+            && self.backoff == other.backoff
+            && self.double_check == other.double_check
+            && self.node_id == other.node_id
+            && self.resolution_order == other.resolution_order
+            && self.prewarm == other.prewarm
+    }
+}
+
+impl<'a, T: Eq> Eq for CachedProcess<'a, T> {}
+
+/// Formats the process name and state of the cache, such as `counter-process [present]`, without looking up the process.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::CachedProcess;
+///
+/// let cache: CachedProcess<'_, usize> = CachedProcess::new("foo");
+/// assert_eq!(cache.to_string(), "foo [unresolved]");
+///
+/// cache.get_with(|_| None);
+/// assert_eq!(cache.to_string(), "foo [absent]");
+///
+/// cache.set(1);
+/// assert_eq!(cache.to_string(), "foo [present]");
+/// ```
+impl<T> fmt::Display for CachedProcess<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state() {
+            CacheState::NotLookedUp => "unresolved",
+            CacheState::Absent => "absent",
+            CacheState::Present => "present",
+        };
+        write!(f, "{} [{state}]", self.process_name())
+    }
+}
+
+/// A cache is equal to a process if it looks up to that process.
+///
+/// This looks up the process like [`CachedLookup::get`], so the result is cached.
+///
+/// # Example
+///
+/// ```
+/// use lunatic::Process;
+/// use lunatic_cached_process::{CachedLookup, CachedProcess, ProcessCached};
+///
+/// let cache: ProcessCached<'_, ()> = CachedProcess::new("foo");
+/// cache.set(Process::this());
+/// assert!(cache == Process::this());
+/// ```
+impl<T> PartialEq<T> for CachedProcess<'_, T>
+where
+    T: PartialEq,
+    for<'b> Self: CachedLookup<'b, T>,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.get().is_some_and(|process| &process == other)
+    }
+}
+
+impl<'a, T> Default for CachedProcess<'a, T> {
+    /// Returns a process cache with an empty process name.
+    fn default() -> Self {
+        CachedProcess::new_const("")
+    }
+}
+
+/// Builder for configuring a [`CachedProcess`].
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lunatic_cached_process::{CachedProcessBuilder, ProcessCached};
+///
+/// let cache: ProcessCached<'_, ()> = CachedProcessBuilder::new("foo")
+///     .ttl(Duration::from_secs(5))
+///     .retries(3)
+///     .backoff(Duration::from_millis(10))
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachedProcessBuilder<'a, T> {
+    process_name: Option<Cow<'a, str>>,
+    fallback_names: &'a [&'a str],
+    ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    max_negative_ttl: Option<Duration>,
+    retries: u32,
+    backoff: Option<Duration>,
+    double_check: bool,
+    liveness_check: Option<fn(&T) -> bool>,
+    on_dead: Option<fn(&str)>,
+    resolver: Option<fn(&str) -> Option<T>>,
+    name_transform: Option<fn(&str) -> String>,
+    on_transition: Option<fn(&str, CacheState, CacheState)>,
+    prewarm: bool,
+    resolution_order: &'a [ResolutionSource],
+    clock: Option<&'a dyn Clock>,
+}
+
+impl<'a, T> CachedProcessBuilder<'a, T> {
+    /// Construct a new builder with a registered process name.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
+        CachedProcessBuilder {
+            process_name: Some(name.into()),
+            fallback_names: &[],
+            ttl: None,
+            negative_ttl: None,
+            max_negative_ttl: None,
+            retries: 0,
+            backoff: None,
+            double_check: false,
+            liveness_check: None,
+            on_dead: None,
+            resolver: None,
+            name_transform: None,
+            on_transition: None,
+            prewarm: false,
+            resolution_order: &[],
+            clock: None,
+        }
+    }
+
+    /// Construct a new builder for a cache without a process name.
+    ///
+    /// See [`CachedProcess::unnamed`].
+    pub fn unnamed() -> Self {
+        CachedProcessBuilder {
+            process_name: None,
+            ..CachedProcessBuilder::new("")
+        }
+    }
+
+    /// Sets the names to look up in order if the process name isn't found.
+    ///
+    /// See [`CachedProcess::new_with_fallbacks`].
+    pub fn fallback_names(mut self, fallback_names: &'a [&'a str]) -> Self {
+        self.fallback_names = fallback_names;
+        self
+    }
+
+    /// Sets the time-to-live of a found process.
+    ///
+    /// See [`CachedProcess::with_ttl`].
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the time-to-live of a failed lookup.
+    ///
+    /// See [`CachedProcess::with_negative_ttl`].
+    pub fn negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = Some(negative_ttl);
+        self
+    }
+
+    /// Sets the maximum time-to-live of consecutive failed lookups.
+    ///
+    /// See [`CachedProcess::with_max_negative_ttl`].
+    pub fn max_negative_ttl(mut self, max_negative_ttl: Duration) -> Self {
+        self.max_negative_ttl = Some(max_negative_ttl);
+        self
+    }
+
+    /// Sets the number of times a failed lookup is retried.
+    ///
+    /// See [`CachedProcess::with_retries`].
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the duration to wait between retries.
+    ///
+    /// See [`CachedProcess::with_backoff`].
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Sets whether a failed lookup is checked once more before caching the process as absent.
+    ///
+    /// See [`CachedProcess::with_double_check`].
+    pub fn double_check(mut self, double_check: bool) -> Self {
+        self.double_check = double_check;
+        self
+    }
+
+    /// Sets the check used to determine if a cached process is still alive.
+    ///
+    /// See [`CachedProcess::with_liveness_check`].
+    pub fn liveness_check(mut self, is_alive: fn(&T) -> bool) -> Self {
+        self.liveness_check = Some(is_alive);
+        self
+    }
+
+    /// Sets the hook called when a cached process fails the liveness check.
+    ///
+    /// See [`CachedProcess::with_on_dead`].
+    pub fn on_dead(mut self, on_dead: fn(&str)) -> Self {
+        self.on_dead = Some(on_dead);
+        self
+    }
+
+    /// Sets the function used to look up processes instead of the lunatic registry.
+    ///
+    /// See [`CachedProcess::with_resolver`].
+    pub fn resolver(mut self, resolver: fn(&str) -> Option<T>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Sets the transform applied to names when looking up the process.
+    ///
+    /// See [`CachedProcess::with_name_transform`].
+    pub fn name_transform(mut self, name_transform: fn(&str) -> String) -> Self {
+        self.name_transform = Some(name_transform);
+        self
+    }
+
+    /// Sets the sources a found process is accepted from, in priority order.
+    ///
+    /// See [`ProcessCached::with_resolution_order`].
+    pub fn resolution_order(mut self, resolution_order: &'a [ResolutionSource]) -> Self {
+        self.resolution_order = resolution_order;
+        self
+    }
+
+    /// Sets a hook called when the state of the cache changes.
+    ///
+    /// See [`CachedProcess::with_on_transition`].
+    pub fn on_transition(mut self, on_transition: fn(&str, CacheState, CacheState)) -> Self {
+        self.on_transition = Some(on_transition);
+        self
+    }
+
+    /// Sets whether the cache is looked up by [`init`] at process startup.
+    ///
+    /// See [`CachedProcess::with_prewarm`].
+    pub fn prewarm(mut self, prewarm: bool) -> Self {
+        self.prewarm = prewarm;
+        self
+    }
+
+    /// Sets the clock used to expire lookups.
+    ///
+    /// See [`CachedProcess::with_clock`].
+    pub fn clock(mut self, clock: &'a dyn Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Builds the [`CachedProcess`].
+    pub fn build(self) -> CachedProcess<'a, T> {
+        CachedProcess {
+            fallback_names: self.fallback_names,
+            ttl: self.ttl,
+            negative_ttl: self.negative_ttl,
+            max_negative_ttl: self.max_negative_ttl,
+            retries: self.retries,
+            backoff: self.backoff,
+            double_check: self.double_check,
+            liveness_check: self.liveness_check,
+            on_dead: self.on_dead,
+            resolver: self.resolver,
+            name_transform: self.name_transform,
+            on_transition: self.on_transition,
+            prewarm: self.prewarm,
+            resolution_order: self.resolution_order,
+            clock: self.clock,
+            process_name: ProcessName::new(self.process_name),
+            ..CachedProcess::new_const("")
+        }
+    }
+}
+
+/// Cache effectiveness counters of a [`CachedProcess`].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups served from the cache.
+    pub hits: u64,
+    /// Number of lookups which were made to the lunatic runtime.
+    pub misses: u64,
+    /// Number of lookups made to the lunatic runtime where the process was not found.
+    pub absent: u64,
+    /// How long the last lookup made to the lunatic runtime took, including retries.
+    pub last_lookup_duration: Option<Duration>,
+}
+
+/// Process name and state of a cache, returned by [`CachedProcess::export`].
+///
+/// The cached process isn't included, so a snapshot can be kept across a restart
+/// and imported with [`CachedProcess::import`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheSnapshot {
+    /// Process name of the cache.
+    pub name: String,
+    /// State of the cache when it was exported.
+    pub state: CacheState,
+}
+
+/// Process returned by [`CachedLookup::handle`], which derefs to the process.
+///
+/// The handle holds its own copy of the process, so it can be kept around without borrowing the cache.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct CachedHandle<T>(T);
+
+impl<T> CachedHandle<T> {
+    /// Returns the process.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for CachedHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for CachedHandle<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Process cache which is hashed and compared by its process name, returned by [`CachedProcess::by_name`].
+///
+/// This allows caches to be used as keys in a [`HashMap`](std::collections::HashMap),
+/// independently of their cached process. Keys can also be looked up by their process name.
+/// Clippy's `mutable_key_type` lint can be allowed for these maps, as the cached process isn't hashed.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use lunatic_cached_process::{CachedProcess, ProcessCached};
+///
+/// let cache: ProcessCached<'_, ()> = CachedProcess::new("foo");
+/// let mut owners = HashMap::new();
+/// owners.insert(cache.by_name(), "team-a");
+/// assert_eq!(owners.get("foo"), Some(&"team-a"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ByName<'a, T>(CachedProcess<'a, T>);
+
+impl<'a, T> ByName<'a, T> {
+    /// Returns the process cache.
+    pub fn into_inner(self) -> CachedProcess<'a, T> {
+        self.0
+    }
+}
+
+impl<'a, T> std::ops::Deref for ByName<'a, T> {
+    type Target = CachedProcess<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> std::hash::Hash for ByName<'_, T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.process_name().hash(state);
+    }
+}
+
+impl<T> PartialEq for ByName<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.process_name() == other.0.process_name()
+    }
+}
+
+impl<T> Eq for ByName<'_, T> {}
+
+impl<T> std::borrow::Borrow<str> for ByName<'_, T> {
+    fn borrow(&self) -> &str {
+        self.0.process_name()
+    }
+}
+
+/// State of a process cache, returned by [`CachedProcess::state`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum CacheState {
+    /// The process hasn't been looked up yet.
+    NotLookedUp,
+    /// The process was looked up, and wasn't found.
+    Absent,
+    /// The process was found, or was set with [`CachedLookup::set`].
+    Present,
+}
+
+/// Result of the last lookup of a cache, returned by [`CachedProcess::resolve`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Resolution<T> {
+    /// The process hasn't been looked up yet, or the cached process has expired.
+    NotResolved,
+    /// The process was looked up, and wasn't found.
+    Absent,
+    /// The process was found, or was set with [`CachedLookup::set`].
+    Present(T),
+}
+
+impl<T> Resolution<T> {
+    /// Returns true if the process was looked up, regardless of whether it was found.
+    pub fn is_resolved(&self) -> bool {
+        !matches!(self, Resolution::NotResolved)
+    }
+
+    /// Returns the process, if it was found.
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            Resolution::Present(process) => Some(process),
+            Resolution::NotResolved | Resolution::Absent => None,
+        }
+    }
+}
+
+/// Source a process is accepted from, set with [`ProcessCached::with_resolution_order`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ResolutionSource {
+    /// A process registered on the current node.
+    Local,
+    /// A process registered on the node with the id.
+    Node(u64),
+    /// A process registered on any node.
+    Any,
+}
+
+impl ResolutionSource {
+    /// Returns true if a process registered on the node with `node_id` is accepted from this source.
+    pub fn accepts(&self, node_id: u64) -> bool {
+        match self {
+            ResolutionSource::Local => node_id == lunatic::distributed::node_id(),
+            ResolutionSource::Node(id) => node_id == *id,
+            ResolutionSource::Any => true,
+        }
+    }
+}
+
+/// Kind of process held by a cache, returned by [`CachedLookup::resolver_kind`].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResolverKind {
+    /// The cache holds a [`Process`].
+    Process,
+    /// The cache holds a [`ProcessRef`] of an abstract process.
+    ProcessRef,
+    /// The cache holds another kind of handle, such as a cache implemented outside of this crate.
+    Other,
+}
+
+/// Error returned when a cached lookup doesn't resolve to a process.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LookupError {
+    /// No process is registered under the process name.
+    NotRegistered,
+    /// The registered process failed the liveness check.
+    Dead,
+    /// The cache was disabled with [`CachedProcess::disable`].
+    Disabled,
+    /// The cache was constructed with [`CachedProcess::unnamed`], and no name has been set.
+    Unnamed,
+    /// The process was looked up by the resolver of the same cache while it was resolving the process.
+    Reentrant,
+    /// The lookup didn't complete in time, see [`SharedCachedProcess::get_with_timeout`].
+    TimedOut,
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::NotRegistered => write!(f, "process is not registered"),
+            LookupError::Dead => write!(f, "process is dead"),
+            LookupError::Disabled => write!(f, "process cache is disabled"),
+            LookupError::Unnamed => write!(f, "process cache has no name"),
+            LookupError::Reentrant => write!(f, "process cache is already resolving the process"),
+            LookupError::TimedOut => write!(f, "process lookup timed out"),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+/// Trait for accessing a process cache.
+///
+/// This is implemented for static process local caches declared with [`cached_process!`],
+/// as well as [`CachedProcess`] itself, which allows for short lived caches without a static.
+/// Caches shared behind an [`Rc`] or [`Arc`] can be accessed without dereferencing them.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{CachedLookup, ProcessCached};
+///
+/// let cache: ProcessCached<'_, ()> = ProcessCached::new("foo");
+/// for _ in 0..3 {
+///     cache.get(); // Only the first call looks up the process from the lunatic runtime
+/// }
+/// assert_eq!(cache.stats().misses, 1);
+/// ```
+pub trait CachedLookup<'a, T> {
+    /// Looks up a process by its name, and caches the result.
+    /// Subsequent calls will used the cached value.
+    fn get(&'a self) -> Option<T>;
+
+    /// Looks up a process like [`CachedLookup::get`], returning an error describing why the process is unavailable.
+    ///
+    /// By default, a process which isn't found is reported as [`LookupError::NotRegistered`].
+    #[inline]
+    fn try_get(&'a self) -> Result<T, LookupError> {
+        self.get().ok_or(LookupError::NotRegistered)
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], panicking if the process isn't found.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a message including the process name if the process isn't found.
+    #[inline]
+    #[track_caller]
+    fn get_expect(&'a self) -> T {
+        match self.try_get() {
+            Ok(process) => process,
+            Err(err) => panic!("failed to lookup process '{}': {err}", self.process_name()),
+        }
+    }
+
+    /// Returns the process name.
+    ///
+    /// Defaults to the type name of the cache.
+    #[inline]
+    fn process_name(&'a self) -> &'a str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], and links it to the current process when it's first resolved.
+    ///
+    /// The current process should catch link failures with [`lunatic::Mailbox::catch_link_failure`],
+    /// otherwise it will die along with the linked process.
+    /// When a [`lunatic::MailboxResult::LinkDied`] message is received, pass its tag to [`reset_linked`]
+    /// (or [`CachedProcess::reset_if_linked`]) so the next call looks up the process again.
+    ///
+    /// By default the process is looked up like [`CachedLookup::get`] without being linked.
+    #[inline]
+    fn get_linked(&'a self) -> Option<T> {
+        self.get()
+    }
+
+    /// Sets the cached lookup. This will prevent any lookups from being made,
+    /// since subsequent calls to [`CachedLookup::get`] will return this cached value.
+    fn set(&'a self, value: T);
+
+    /// Sets the cached lookup like [`CachedLookup::set`], but only if the process hasn't been looked up yet,
+    /// or was looked up and not found. Returns `true` if the value was stored.
+    ///
+    /// A cached process is never overwritten, even if its ttl has expired.
+    ///
+    /// By default the process is looked up like [`CachedLookup::get`], and the value is only stored if it wasn't found.
+    #[inline]
+    fn set_if_absent(&'a self, value: T) -> bool {
+        if self.get().is_some() {
+            return false;
+        }
+        self.set(value);
+        true
+    }
+
+    /// Resets the cache, causing the next call to [`CachedLookup::get`] to lookup the process again.
+    ///
+    /// If the cached process was linked with [`CachedLookup::get_linked`], it's unlinked from the current process.
+    /// Resetting a linked cache is the correct way to stop monitoring the process.
+    fn reset(&'a self);
+
+    /// Looks up the process again regardless of what is cached, and caches the result.
+    ///
+    /// This is equivalent to calling [`CachedLookup::reset`] followed by [`CachedLookup::get`].
+    #[inline]
+    fn refresh(&'a self) -> Option<T> {
+        self.reset();
+        self.get()
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], also returning `true` if this call looked up the process
+    /// rather than using the cached value.
+    /// By default, the process counts as looked up unless [`CachedLookup::peek`] returns it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "get-fresh-doc";
+    /// }
+    ///
+    /// assert_eq!(FOO.get_fresh(), (None, true));
+    /// assert_eq!(FOO.get_fresh(), (None, false)); // Cached as absent
+    /// ```
+    #[inline]
+    fn get_fresh(&'a self) -> (Option<T>, bool) {
+        match self.peek() {
+            Some(process) => (Some(process), false),
+            None => (self.get(), true),
+        }
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], but checks that a cached process is still alive before returning it.
+    ///
+    /// If the cached process is dead, the cache is reset and the process is looked up again.
+    /// If the newly looked up process is also dead, the cache is reset and `None` is returned.
+    ///
+    /// Liveness is determined by the check provided to [`CachedProcess::with_liveness_check`].
+    #[inline]
+    fn get_or_reset_if_dead(&'a self) -> Option<T> {
+        self.try_get_or_reset_if_dead().ok()
+    }
+
+    /// Looks up a process like [`CachedLookup::get_or_reset_if_dead`],
+    /// returning an error describing why the process is unavailable.
+    ///
+    /// By default the process is looked up like [`CachedLookup::try_get`] without a liveness check.
+    #[inline]
+    fn try_get_or_reset_if_dead(&'a self) -> Result<T, LookupError> {
+        self.try_get()
+    }
+
+    /// Looks up the process from the lunatic runtime without reading or updating the cache.
+    ///
+    /// This is useful for comparing the cached process with the currently registered process.
+    /// Returns `None` by default, so the process is only looked up through the cache.
+    #[inline]
+    fn lookup_uncached(&'a self) -> Option<T> {
+        None
+    }
+
+    /// Returns the cached process without looking it up.
+    ///
+    /// Unlike [`CachedLookup::get`], this never makes a call to the lunatic runtime,
+    /// and returns `None` if the process hasn't been looked up yet, wasn't found, or its ttl has expired.
+    /// Returns `None` by default, since the cache can't be read without looking up the process.
+    #[inline]
+    fn peek(&'a self) -> Option<T> {
+        None
+    }
+
+    /// Returns whether the cache holds a [`Process`] or a [`ProcessRef`].
+    ///
+    /// This is useful for labelling caches in logs and dashboards.
+    /// Returns [`ResolverKind::Other`] by default.
+    #[inline]
+    fn resolver_kind(&'a self) -> ResolverKind {
+        ResolverKind::Other
+    }
+
+    /// Returns the current time of the clock used by the cache, set with [`CachedProcess::with_clock`].
+    #[inline]
+    fn now(&'a self) -> Instant {
+        Instant::now()
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], but only uses the cached process if `still_valid` returns `true` for it.
+    ///
+    /// If the predicate fails, the cache is reset and the process is looked up again.
+    /// The predicate is only called with a cached process, not with a newly looked up one.
+    #[inline]
+    fn get_if<F>(&'a self, still_valid: F) -> Option<T>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        if self.peek().is_some_and(|process| !still_valid(&process)) {
+            self.reset();
+        }
+        self.get()
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], returning a [`CachedHandle`] which derefs to the process.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::spawn_link;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<String> = "foo";
+    /// }
+    ///
+    /// spawn_link!(|mailbox: Mailbox<String>| { loop { mailbox.receive(); } }).register("foo");
+    ///
+    /// if let Some(foo) = FOO.handle() {
+    ///     foo.send("hello".to_string());
+    /// }
+    /// ```
+    #[inline]
+    fn handle(&'a self) -> Option<CachedHandle<T>> {
+        self.get().map(CachedHandle)
+    }
+
+    /// Looks up a process like [`CachedLookup::get`] from async code.
+    ///
+    /// Processes are looked up from the local registry, so the future completes immediately when polled.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn get_async(&'a self) -> impl std::future::Future<Output = Option<T>> {
+        async move { self.get() }
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], and maps it with `f` if it was found.
+    ///
+    /// Only the process is cached, the mapped value is computed on every call.
+    #[inline]
+    fn get_map<U, F>(&'a self, f: F) -> Option<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        self.get().map(f)
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], and only if it was found, looks up the process of `other`,
+    /// which depends on it being up.
+    ///
+    /// Returns both processes if both were found. Each process is looked up from its own cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static DATABASE: Process<()> = "database";
+    ///     static API: Process<()> = "api";
+    /// }
+    ///
+    /// assert!(DATABASE.and_then_cache(&API).is_none());
+    /// assert!(!API.with(|process| process.is_looked_up())); // The database wasn't found
+    /// ```
+    #[inline]
+    fn and_then_cache<'b, U, C>(&'a self, other: &'b C) -> Option<(T, U)>
+    where
+        C: CachedLookup<'b, U> + ?Sized,
+    {
+        let process = self.get()?;
+        Some((process, other.get()?))
+    }
+
+    /// Looks up a process like [`CachedLookup::get`], returning `fallback` if it wasn't found.
+    ///
+    /// A cached process is returned as is. Otherwise the process is looked up like [`CachedLookup::lookup_uncached`],
+    /// so neither the fallback nor a failed lookup is cached, and a process registered later is still picked up
+    /// by the next call. The cache is left untouched, including a cached absence and a disabled cache.
+    /// Use [`CachedLookup::set`] instead to cache a process permanently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static FOO: Process<()> = "foo";
+    /// }
+    ///
+    /// let stub = Process::this();
+    /// assert_eq!(FOO.get_or(stub), stub);
+    /// assert!(!FOO.with(|process| process.is_looked_up()));
+    /// ```
+    #[inline]
+    fn get_or(&'a self, fallback: T) -> T {
+        self.peek()
+            .or_else(|| self.lookup_uncached())
+            .unwrap_or(fallback)
+    }
+
+    /// Waits until the process is present, looking it up again every `poll` until `timeout` has elapsed.
+    ///
+    /// The process is cached once found, so later calls return immediately without waiting.
+    /// Returns `None` if the process wasn't found before the timeout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use lunatic::Process;
+    /// use lunatic_cached_process::{cached_process, CachedLookup};
+    ///
+    /// cached_process! {
+    ///     static DEPENDENCY: Process<()> = "wait-until-present-doc";
+    /// }
+    ///
+    /// assert!(DEPENDENCY
+    ///     .wait_until_present(Duration::from_millis(20), Duration::from_millis(5))
+    ///     .is_none());
+    ///
+    /// Process::<()>::this().register("wait-until-present-doc");
+    /// assert!(DEPENDENCY
+    ///     .wait_until_present(Duration::from_secs(1), Duration::from_millis(5))
+    ///     .is_some());
+    /// ```
+    fn wait_until_present(&'a self, timeout: Duration, poll: Duration) -> Option<T> {
+        if let Some(process) = self.get() {
+            return Some(process);
+        }
+
+        let started_at = self.now();
+        loop {
+            let remaining =
+                timeout.saturating_sub(self.now().saturating_duration_since(started_at));
+            if remaining.is_zero() {
+                return None;
+            }
+            lunatic::sleep(poll.min(remaining));
+            if let Some(process) = self.refresh() {
+                return Some(process);
+            }
+        }
+    }
+}
+
+impl<T, C> CachedLookup<'static, T> for ProcessLocal<C>
+where
+    C: CachedLookup<'static, T>,
+{
+    #[inline]
+    fn get(&'static self) -> Option<T> {
+        self.with(CachedLookup::get)
+    }
+
+    #[inline]
+    fn try_get(&'static self) -> Result<T, LookupError> {
+        self.with(CachedLookup::try_get)
+    }
+
+    #[inline]
+    fn process_name(&'static self) -> &'static str {
+        self.with(CachedLookup::process_name)
+    }
+
+    #[inline]
+    fn get_linked(&'static self) -> Option<T> {
+        self.with(CachedLookup::get_linked)
+    }
+
+    #[inline]
+    fn set(&'static self, value: T) {
+        self.with(|proc| CachedLookup::set(proc, value))
+    }
+
+    #[inline]
+    fn set_if_absent(&'static self, value: T) -> bool {
+        self.with(|proc| CachedLookup::set_if_absent(proc, value))
+    }
+
+    #[inline]
+    fn reset(&'static self) {
+        self.with(CachedLookup::reset)
+    }
+
+    #[inline]
+    fn refresh(&'static self) -> Option<T> {
+        self.with(CachedLookup::refresh)
+    }
+
+    #[inline]
+    fn get_fresh(&'static self) -> (Option<T>, bool) {
+        self.with(CachedLookup::get_fresh)
+    }
+
+    #[inline]
+    fn try_get_or_reset_if_dead(&'static self) -> Result<T, LookupError> {
+        self.with(CachedLookup::try_get_or_reset_if_dead)
+    }
+
+    #[inline]
+    fn lookup_uncached(&'static self) -> Option<T> {
+        self.with(CachedLookup::lookup_uncached)
+    }
+
+    #[inline]
+    fn peek(&'static self) -> Option<T> {
+        self.with(CachedLookup::peek)
+    }
+
+    #[inline]
+    fn resolver_kind(&'static self) -> ResolverKind {
+        self.with(CachedLookup::resolver_kind)
+    }
+
+    #[inline]
+    fn now(&'static self) -> Instant {
+        self.with(CachedLookup::now)
+    }
+}
+
+/// Implements [`CachedLookup`] for a smart pointer to a cache, delegating to the inner cache.
+macro_rules! impl_cached_lookup_for_pointer {
+    ($pointer:ident) => {
+        impl<'a, T, C> CachedLookup<'a, T> for $pointer<C>
+        where
+            C: CachedLookup<'a, T>,
+        {
+            #[inline]
+            fn get(&'a self) -> Option<T> {
+                (**self).get()
+            }
+
+            #[inline]
+            fn try_get(&'a self) -> Result<T, LookupError> {
+                (**self).try_get()
+            }
+
+            #[inline]
+            fn process_name(&'a self) -> &'a str {
+                (**self).process_name()
+            }
+
+            #[inline]
+            fn get_linked(&'a self) -> Option<T> {
+                (**self).get_linked()
+            }
+
+            #[inline]
+            fn set(&'a self, value: T) {
+                (**self).set(value)
+            }
+
+            #[inline]
+            fn set_if_absent(&'a self, value: T) -> bool {
+                (**self).set_if_absent(value)
+            }
+
+            #[inline]
+            fn reset(&'a self) {
+                (**self).reset()
+            }
+
+            #[inline]
+            fn refresh(&'a self) -> Option<T> {
+                (**self).refresh()
+            }
+
+            #[inline]
+            fn get_fresh(&'a self) -> (Option<T>, bool) {
+                (**self).get_fresh()
+            }
+
+            #[inline]
+            fn try_get_or_reset_if_dead(&'a self) -> Result<T, LookupError> {
+                (**self).try_get_or_reset_if_dead()
+            }
+
+            #[inline]
+            fn lookup_uncached(&'a self) -> Option<T> {
+                (**self).lookup_uncached()
+            }
+
+            #[inline]
+            fn peek(&'a self) -> Option<T> {
+                (**self).peek()
+            }
+
+            #[inline]
+            fn resolver_kind(&'a self) -> ResolverKind {
+                (**self).resolver_kind()
+            }
+
+            #[inline]
+            fn now(&'a self) -> Instant {
+                (**self).now()
+            }
+        }
+    };
+}
+
+impl_cached_lookup_for_pointer!(Rc);
+impl_cached_lookup_for_pointer!(Arc);
+
+impl<'a, T, S> CachedLookup<'a, Process<T, S>> for ProcessCached<'_, T, S> {
+    #[inline]
+    fn get(&'a self) -> Option<Process<T, S>> {
+        self.try_get().ok()
+    }
+
+    #[inline]
+    fn try_get(&'a self) -> Result<Process<T, S>, LookupError> {
+        lookup(self, self.process_resolver())
+    }
+
+    #[inline]
+    fn process_name(&'a self) -> &'a str {
+        CachedProcess::process_name(self)
+    }
+
+    #[inline]
+    fn get_linked(&'a self) -> Option<Process<T, S>> {
+        let process = self.get()?;
+        self.link(process.id());
+        Some(process)
+    }
+
+    #[inline]
+    fn set(&'a self, value: Process<T, S>) {
+        CachedProcess::set(self, value)
+    }
+
+    #[inline]
+    fn set_if_absent(&'a self, value: Process<T, S>) -> bool {
+        CachedProcess::set_if_absent(self, value)
+    }
+
+    #[inline]
+    fn reset(&'a self) {
+        CachedProcess::reset(self)
+    }
+
+    #[inline]
+    fn refresh(&'a self) -> Option<Process<T, S>> {
+        resolve(self, self.process_resolver()).ok()
+    }
+
+    #[inline]
+    fn get_fresh(&'a self) -> (Option<Process<T, S>>, bool) {
+        lookup_fresh(self, || self.get())
+    }
+
+    #[inline]
+    fn try_get_or_reset_if_dead(&'a self) -> Result<Process<T, S>, LookupError> {
+        lookup_alive(self, self.process_resolver())
+    }
+
+    #[inline]
+    fn lookup_uncached(&'a self) -> Option<Process<T, S>> {
+        resolve_uncached(self, self.process_resolver())
+    }
+
+    #[inline]
+    fn peek(&'a self) -> Option<Process<T, S>> {
+        peek(self)
+    }
+
+    #[inline]
+    fn resolver_kind(&'a self) -> ResolverKind {
+        ResolverKind::Process
+    }
+
+    #[inline]
+    fn now(&'a self) -> Instant {
+        CachedProcess::now(self)
+    }
+}
+
+impl<'a, T> CachedLookup<'a, ProcessRef<T>> for ProcessRefCached<'_, T> {
+    #[inline]
+    fn get(&'a self) -> Option<ProcessRef<T>> {
+        self.try_get().ok()
+    }
+
+    #[inline]
+    fn try_get(&'a self) -> Result<ProcessRef<T>, LookupError> {
+        lookup(self, self.resolver(ProcessRef::lookup))
+    }
+
+    #[inline]
+    fn process_name(&'a self) -> &'a str {
+        CachedProcess::process_name(self)
+    }
+
+    #[inline]
+    fn get_linked(&'a self) -> Option<ProcessRef<T>> {
+        let process = self.get()?;
+        self.link(process.id());
+        Some(process)
+    }
+
+    #[inline]
+    fn set(&'a self, value: ProcessRef<T>) {
+        CachedProcess::set(self, value)
+    }
+
+    #[inline]
+    fn set_if_absent(&'a self, value: ProcessRef<T>) -> bool {
+        CachedProcess::set_if_absent(self, value)
+    }
+
+    #[inline]
+    fn reset(&'a self) {
+        CachedProcess::reset(self)
+    }
+
+    #[inline]
+    fn refresh(&'a self) -> Option<ProcessRef<T>> {
+        resolve(self, self.resolver(ProcessRef::lookup)).ok()
+    }
+
+    #[inline]
+    fn get_fresh(&'a self) -> (Option<ProcessRef<T>>, bool) {
+        lookup_fresh(self, || self.get())
+    }
+
+    #[inline]
+    fn try_get_or_reset_if_dead(&'a self) -> Result<ProcessRef<T>, LookupError> {
+        lookup_alive(self, self.resolver(ProcessRef::lookup))
+    }
+
+    #[inline]
+    fn lookup_uncached(&'a self) -> Option<ProcessRef<T>> {
+        resolve_uncached(self, self.resolver(ProcessRef::lookup))
+    }
+
+    #[inline]
+    fn peek(&'a self) -> Option<ProcessRef<T>> {
+        peek(self)
+    }
+
+    #[inline]
+    fn resolver_kind(&'a self) -> ResolverKind {
+        ResolverKind::ProcessRef
+    }
+
+    #[inline]
+    fn now(&'a self) -> Instant {
+        CachedProcess::now(self)
+    }
+}
+
+/// Macro for defining a process local lookup cache for processes.
+///
+/// The structure is as follows:
+///
+/// ```text
+/// static <ident>: <process_type> = <process_name>;
+/// ```
+///
+/// Where
+///
+/// - `<ident>`: Static variable name.
+/// - `<process_type>`: Either `Process<T>`, `ProcessRef<T>`, or `Process<T, S>` where `T` is the message type, and `S` is the serializer.
+/// - `<process_name>`: The process name, either as a string literal or an expression evaluating to a `&'static str` or `String`.
+///   Expressions are evaluated the first time the cache is accessed in each process.
+///
+/// Optionally, a time-to-live can be specified with `static <ident>: <process_type> = <process_name>, ttl = <duration>;`,
+/// where `<duration>` is an expression evaluating to a [`std::time::Duration`]. See [`CachedProcess::with_ttl`].
+/// Similarly, `, negative_ttl = <duration>` can be appended to expire failed lookups. See [`CachedProcess::with_negative_ttl`].
+///
+/// Other options can be set with a trailing config block, `static <ident>: <process_type> = <process_name>, { <option>: <value>, ... };`,
+/// where each option is a method of [`CachedProcessBuilder`], such as `ttl`, `negative_ttl`, `retries`, `backoff`, or `liveness_check`.
+///
+/// A group function can be declared before the caches with `fn <group>();`, which looks up every cache in the block
+/// and returns a tuple of their results, in declaration order.
+///
+/// A prefix can be declared at the start of the block with `prefix = <prefix>;`, which is prepended to every process name in the block.
+/// The prefix is concatenated when each cache is first accessed, so prefixed names are always owned `String`s, even for literal names.
+///
+/// # Examples
+///
+/// Cached [`lunatic::Process`].
+///
+/// ```
+/// use lunatic_cached_process::cached_process;
+/// use serde::{Serialize, Deserialize};
+///
+/// cached_process! {
+///     static COUNTER: Process<CountMessage> = "global-counter-process";
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// enum CountMessage {
+///     Inc,
+///     Dec,
+/// }
+/// ```
+///
+/// Cached [`lunatic::Process`] with a name computed at runtime.
+///
+/// ```
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static SHARD: Process<()> = format!("shard-{}", std::env::var("SHARD_ID").unwrap_or_default());
+/// }
+/// ```
+///
+/// Cached [`lunatic::Process`] which is looked up again after 5 seconds.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "global-counter-process", ttl = Duration::from_secs(5);
+/// }
+/// ```
+///
+/// Cached [`lunatic::Process`] where a failed lookup is retried after 100 milliseconds.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "global-counter-process", negative_ttl = Duration::from_millis(100);
+/// }
+/// ```
+///
+/// Cached [`lunatic::Process`] configured with a config block.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "global-counter-process", {
+///         ttl: Duration::from_secs(5),
+///         retries: 3,
+///         backoff: Duration::from_millis(10),
+///     };
+/// }
+/// ```
+///
+/// Cached [`lunatic::Process`] without a name, which is set at runtime with [`CachedProcess::set_name`].
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, CachedLookup};
+///
+/// cached_process! {
+///     static PLUGIN: Process<()>;
+/// }
+///
+/// assert!(PLUGIN.get().is_none());
+/// PLUGIN.with(|process| process.set_name("plugin-process"));
+/// assert_eq!(PLUGIN.process_name(), "plugin-process");
+/// ```
+///
+/// Caches with a shared name prefix, looking up `"app::counter"` and `"app::logger"`.
+///
+/// ```
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     prefix = "app::";
+///
+///     static COUNTER: Process<()> = "counter";
+///     static LOGGER: Process<String> = "logger";
+/// }
+///
+/// assert_eq!(COUNTER.with(|process| process.process_name().to_string()), "app::counter");
+/// ```
+///
+/// Group function for looking up multiple caches at once.
+///
+/// ```
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     pub fn dependencies();
+///
+///     static COUNTER: Process<()> = "global-counter-process";
+///     static LOGGER: Process<String> = "global-logger-process";
+/// }
+///
+/// let (counter, logger) = dependencies();
+/// assert!(counter.is_none());
+/// assert!(logger.is_none());
+///
+/// // Names of the caches holding a process, without looking them up
+/// assert!(dependencies_present_names().is_empty());
+/// ```
+///
+/// Cached [`lunatic::process::ProcessRef`].
+///
+/// ```
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: ProcessRef<CounterProcess> = "global-counter-process-ref";
+/// }
+///
+/// struct CounterProcess;
+///
+/// impl lunatic::process::AbstractProcess for CounterProcess {
+///     type Arg = ();
+///     type State = Self;
+///
+///     fn init(_: lunatic::process::ProcessRef<Self>, _: ()) -> Self {
+///         CounterProcess
+///     }
+/// }
+/// ```
+///
+/// Type aliases for the process handles, named after each static in camel case followed by `Handle`,
+/// are declared with the `handle_aliases;` directive at the start of the block.
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, CachedLookup};
+///
+/// cached_process! {
+///     handle_aliases;
+///
+///     static GLOBAL_COUNTER: Process<u32> = "global-counter-process";
+/// }
+///
+/// let counter: Option<GlobalCounterHandle> = GLOBAL_COUNTER.get();
+/// assert!(counter.is_none());
+/// ```
+///
+/// A serializer can only be specified for [`lunatic::Process`] caches,
+/// as a [`lunatic::process::ProcessRef`] uses the serializer of its abstract process.
+///
+/// ```compile_fail
+/// use lunatic::serializer::Bincode;
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: ProcessRef<CounterProcess, Bincode> = "global-counter-process-ref";
+/// }
+/// # struct CounterProcess;
+/// ```
+///
+/// An empty process name is rejected, as the process could never be looked up.
+/// Names computed at runtime are checked in debug builds.
+///
+/// ```compile_fail
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "";
+/// }
+/// ```
+///
+/// A process name literal declared twice in a block is rejected, as the two statics would be separate caches
+/// of the same process. Computed names aren't checked.
+///
+/// ```compile_fail
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "global-counter-process";
+///     static OTHER_COUNTER: Process<()> = "global-counter-process";
+/// }
+/// ```
+///
+/// Directives must come before the entries, in the order `handle_aliases;`, `prefix = ...;` and then the group function.
+///
+/// ```compile_fail
+/// use lunatic_cached_process::cached_process;
+///
+/// cached_process! {
+///     static COUNTER: Process<()> = "global-counter-process";
+///     handle_aliases;
+/// }
+/// ```
+#[macro_export]
+macro_rules! cached_process {
+    (
+        @checked
+        handle_aliases;
+        $( $rest:tt )+
+    ) => {
+        $crate::__cached_process_handle_aliases! { $( $rest )+ }
+        $crate::cached_process! {
+            @checked $( $rest )+ }
+    };
+    (
+        @checked
+        prefix = $prefix:expr;
+        $(#[$group_attr:meta])* $group_vis:vis fn $group:ident ();
+        $(
+            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        )+
+    ) => {
+        $crate::__cached_process_check_duplicates!($( { $(#[$attr])* $ident $( , $name )? } )+);
+        $crate::cached_process! {
+            @checked
+            $(#[$group_attr])* $group_vis fn $group();
+            $(
+                $(#[$attr])* $vis static $ident : $process_type <$ty $( , $s )?> $( = ::std::format!("{}{}", $prefix, $name) $( , ttl = $ttl )? $( , negative_ttl = $negative_ttl )? $( , { $( $key : $value ),* } )? )? ;
+            )+
+        }
+    };
+    (
+        @checked
+        prefix = $prefix:expr;
+        $(
+            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        )+
+    ) => {
+        $crate::__cached_process_check_duplicates!($( { $(#[$attr])* $ident $( , $name )? } )+);
+        $crate::cached_process! {
+            @checked
+            $(
+                $(#[$attr])* $vis static $ident : $process_type <$ty $( , $s )?> $( = ::std::format!("{}{}", $prefix, $name) $( , ttl = $ttl )? $( , negative_ttl = $negative_ttl )? $( , { $( $key : $value ),* } )? )? ;
+            )+
+        }
+    };
+    (
+        @checked
+        $(#[$group_attr:meta])* $group_vis:vis fn $group:ident ();
+        $(
+            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        )+
+    ) => {
+        $crate::cached_process! {
+            @checked
+            $(
+                $(#[$attr])* $vis static $ident : $process_type <$ty $( , $s )?> $( = $name $( , ttl = $ttl )? $( , negative_ttl = $negative_ttl )? $( , { $( $key : $value ),* } )? )? ;
+            )+
+        }
+
+        $(#[$group_attr])*
+        $group_vis fn $group() -> ( $( ::std::option::Option<$crate::__private::$process_type<$ty $( , $s )?>>, )+ ) {
+            ( $( $crate::CachedLookup::get(&$ident), )+ )
+        }
+
+        $crate::paste! {
+            $(#[$group_attr])*
+            #[allow(dead_code)]
+            $group_vis fn [<$group _present_names>]() -> ::std::vec::Vec<&'static str> {
+                $crate::present_names(&[ $( &$ident ),+ ])
+            }
+
+            $(#[$group_attr])*
+            #[allow(dead_code)]
+            $group_vis fn [<$group _init>]() -> usize {
+                $crate::__private::init_group(&[ $( &$ident ),+ ])
+            }
+        }
+    };
+    (
+        @checked
+        $(
+            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        )+
+    ) => {
+        $(
+            $crate::__cached_process_check_name!($(#[$attr])* $ident $( , $name )?);
+        )+
+        $crate::__cached_process_check_duplicates!($( { $(#[$attr])* $ident $( , $name )? } )+);
+
+        $crate::paste! {
+            $(
+                lunatic::process_local! {
+                    $(#[$attr])* $vis static $ident: $crate:: [<$process_type Cached>] <'static, $ty $( , $s )?> = {
+                        $crate::__private::register(&$ident);
+                        $crate::__private::prepare(
+                            $crate::__cached_process_builder!($( $name )?)
+                                $(
+                                    $( .ttl($ttl) )?
+                                    $( .negative_ttl($negative_ttl) )?
+                                    $( $( .$key($value) )* )?
+                                )?
+                                .build()
+                        )
+                    };
+                }
+            )+
+        }
+    };
+    (@checked $( $unexpected:tt )*) => {
+        ::std::compile_error!("unrecognised or misordered cached_process! directive");
+    };
+    ($( $input:tt )+) => {
+        $crate::__cached_process_check_serializers! { [] [] $( $input )+ }
+    };
+}
+
+/// Macro for declaring an array of process caches, registered under a prefix followed by their index.
+///
+/// Each cache is looked up and cached separately, like the caches of the [`cached_process!`] macro.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{cached_process_array, CachedLookup};
+///
+/// cached_process_array! {
+///     static WORKERS: [Process<()>; 8] = "worker-";
+/// }
+///
+/// assert_eq!(WORKERS.len(), 8);
+/// assert_eq!(WORKERS[3].process_name(), "worker-3");
+/// assert!(WORKERS[3].get().is_none()); // Looks up "worker-3"
+/// ```
+#[macro_export]
+macro_rules! cached_process_array {
+    () => {};
+    (
+        $(#[$attr:meta])* $vis:vis static $ident:ident : [ ProcessRef <$ty:ty , $s:ty> ; $len:expr ] = $prefix:expr ;
+        $( $rest:tt )*
+    ) => {
+        $(#[$attr])*
+        ::std::compile_error!(::std::concat!(
+            "`",
+            ::std::stringify!($ident),
+            "` can't specify a serializer, `ProcessRef` uses the serializer of its abstract process"
+        ));
+
+        $crate::cached_process_array! { $( $rest )* }
+    };
+    (
+        $(#[$attr:meta])* $vis:vis static $ident:ident : [ $process_type:ident <$ty:ty $( , $s:ty )?> ; $len:expr ] = $prefix:expr ;
+        $( $rest:tt )*
+    ) => {
+        $crate::paste! {
+            lunatic::process_local! {
+                static [<__ $ident _CACHES>]: [$crate:: [<$process_type Cached>] <'static, $ty $( , $s )?>; $len] = {
+                    $crate::__private::register_array(&[<__ $ident _CACHES>]);
+                    ::std::array::from_fn(|index| {
+                        $crate::__private::prepare(
+                            $crate::CachedProcessBuilder::new(::std::format!("{}{}", $prefix, index)).build()
+                        )
+                    })
+                };
+            }
+
+            $(#[$attr])*
+            $vis static $ident: $crate::CachedProcessArray<$crate::__private::$process_type<$ty $( , $s )?>, $len> =
+                $crate::CachedProcessArray::new(&[<__ $ident _CACHES>]);
+        }
+
+        $crate::cached_process_array! { $( $rest )* }
+    };
+}
+
+/// Constructs the builder of an entry in the cached_process! macro, which is unnamed if no name is given.
+///
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_builder {
+    () => {
+        $crate::CachedProcessBuilder::unnamed()
+    };
+    ($name:expr) => {
+        $crate::CachedProcessBuilder::new($name)
+    };
+}
+
+/// Rejects an empty string literal as the name of an entry in the cached_process! macro.
+///
+/// The attributes of the entry are applied to the check, so entries removed by `#[cfg]` aren't checked.
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_check_name {
+    ($(#[$attr:meta])* $ident:ident) => {};
+    ($(#[$attr:meta])* $ident:ident, $name:expr) => {
+        $(#[$attr])*
+        const _: () = if $crate::__private::is_empty_literal(::std::stringify!($name)) {
+            ::std::panic!(::std::concat!(
+                "the process name of `",
+                ::std::stringify!($ident),
+                "` is empty, so it can never be looked up"
+            ));
+        };
+    };
+}
+
+/// Declares a type alias for the process handle of each entry in the cached_process! macro,
+/// such as `CounterHandle` for `COUNTER`.
+///
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_handle_aliases {
+    (
+        prefix = $prefix:expr;
+        $( $rest:tt )+
+    ) => {
+        $crate::__cached_process_handle_aliases! { $( $rest )+ }
+    };
+    (
+        $(#[$group_attr:meta])* $group_vis:vis fn $group:ident ();
+        $( $rest:tt )+
+    ) => {
+        $crate::__cached_process_handle_aliases! { $( $rest )+ }
+    };
+    (
+        $(
+            $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        )+
+    ) => {
+        $crate::paste! {
+            $(
+                $(#[$attr])*
+                #[doc = ::std::concat!("Process handle cached by [`", ::std::stringify!($ident), "`].")]
+                #[allow(dead_code)]
+                $vis type [<$ident:camel Handle>] = $crate::__private::$process_type<$ty $( , $s )?>;
+            )+
+        }
+    };
+}
+
+/// Rejects string literal process names declared by more than one entry of the cached_process! macro.
+///
+/// Each pair of entries is checked with the attributes of both entries, so entries which can't both exist
+/// because of their `#[cfg]` attributes aren't rejected.
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_check_duplicates {
+    () => {};
+    ($entry:tt $( $rest:tt )*) => {
+        $( $crate::__cached_process_check_duplicate!($entry $rest); )*
+        $crate::__cached_process_check_duplicates!($( $rest )*);
+    };
+}
+
+/// Rejects two entries of the cached_process! macro with the same string literal process name.
+///
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_check_duplicate {
+    (
+        { $(#[$a_attr:meta])* $a:ident, $a_name:expr }
+        { $(#[$b_attr:meta])* $b:ident, $b_name:expr }
+    ) => {
+        $(#[$a_attr])*
+        $(#[$b_attr])*
+        const _: () = if $crate::__private::is_same_literal(::std::stringify!($a_name), ::std::stringify!($b_name)) {
+            ::std::panic!("{}", ::std::concat!(
+                "the process name ",
+                ::std::stringify!($b_name),
+                " of `",
+                ::std::stringify!($b),
+                "` is already used by `",
+                ::std::stringify!($a),
+                "`, so they would be separate caches of the same process"
+            ));
+        };
+    };
+    ($a:tt $b:tt) => {};
+}
+
+/// Rejects a serializer on `ProcessRef` entries of the cached_process! macro, before any entry is expanded.
+///
+/// Rejected entries expand to only the error, so no other errors are reported for them.
+/// The names of the accepted entries are collected, so nothing is expanded if every entry was rejected.
+/// The attributes of the entry are applied to the error, so entries removed by `#[cfg]` aren't rejected.
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cached_process_check_serializers {
+    ([ $( $checked:tt )* ] []) => {};
+    ([ $( $checked:tt )* ] [ $( $accepted:tt )+ ]) => {
+        $crate::cached_process! { @checked $( $checked )* }
+    };
+    ([ $( $checked:tt )* ] [ $( $accepted:tt )* ] handle_aliases; $( $rest:tt )*) => {
+        $crate::__cached_process_check_serializers! { [ $( $checked )* handle_aliases; ] [ $( $accepted )* ] $( $rest )* }
+    };
+    ([ $( $checked:tt )* ] [ $( $accepted:tt )* ] prefix = $prefix:expr; $( $rest:tt )*) => {
+        $crate::__cached_process_check_serializers! { [ $( $checked )* prefix = $prefix; ] [ $( $accepted )* ] $( $rest )* }
+    };
+    ([ $( $checked:tt )* ] [ $( $accepted:tt )* ] $(#[$group_attr:meta])* $group_vis:vis fn $group:ident (); $( $rest:tt )*) => {
+        $crate::__cached_process_check_serializers! { [ $( $checked )* $(#[$group_attr])* $group_vis fn $group(); ] [ $( $accepted )* ] $( $rest )* }
+    };
+    (
+        [ $( $checked:tt )* ] [ $( $accepted:tt )* ]
+        $(#[$attr:meta])* $vis:vis static $ident:ident : ProcessRef <$ty:ty , $s:ty> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        $( $rest:tt )*
+    ) => {
+        $(#[$attr])*
+        ::std::compile_error!(::std::concat!(
+            "`",
+            ::std::stringify!($ident),
+            "` can't specify a serializer, `ProcessRef` uses the serializer of its abstract process"
+        ));
+
+        $crate::__cached_process_check_serializers! { [ $( $checked )* ] [ $( $accepted )* ] $( $rest )* }
+    };
+    (
+        [ $( $checked:tt )* ] [ $( $accepted:tt )* ]
+        $(#[$attr:meta])* $vis:vis static $ident:ident : $process_type:ident <$ty:ty $( , $s:ty )?> $( = $name:expr $( , ttl = $ttl:expr )? $( , negative_ttl = $negative_ttl:expr )? $( , { $( $key:ident : $value:expr ),* $(,)? } )? )? ;
+        $( $rest:tt )*
+    ) => {
+        $crate::__cached_process_check_serializers! {
+            [
+                $( $checked )*
+                $(#[$attr])* $vis static $ident : $process_type <$ty $( , $s )?> $( = $name $( , ttl = $ttl )? $( , negative_ttl = $negative_ttl )? $( , { $( $key : $value ),* } )? )? ;
+            ]
+            [ $( $accepted )* $ident ]
+            $( $rest )*
+        }
+    };
+}
+
+/// This is used internally for the cached_process! macro.
+#[doc(hidden)]
+pub mod __private {
+    pub use lunatic::{process::ProcessRef, Process};
+
+    use lunatic::ProcessLocal;
+
+    use crate::{seed_cache, CachedLookup, CachedLookupDyn, CachedProcess, REGISTRY, SEEDS};
+
+    /// Registers a cache declared with the cached_process! macro, so it can be reset with [`crate::reset_all`]
+    /// and prewarmed with [`crate::init`].
+    pub fn register<T: Clone + 'static>(cache: &'static ProcessLocal<CachedProcess<'static, T>>)
+    where
+        CachedProcess<'static, T>: CachedLookup<'static, T>,
+    {
+        REGISTRY.with(|registry| registry.borrow_mut().push(cache));
+    }
+
+    /// Registers the caches of an array declared with the cached_process_array! macro, so they can be reset with [`crate::reset_all`]
+    /// and prewarmed with [`crate::init`].
+    pub fn register_array<T: Clone + 'static, const N: usize>(
+        caches: &'static ProcessLocal<[CachedProcess<'static, T>; N]>,
+    ) where
+        CachedProcess<'static, T>: CachedLookup<'static, T>,
+    {
+        REGISTRY.with(|registry| registry.borrow_mut().push(caches));
+    }
+
+    /// Looks up each of the caches of a group declared with `prewarm: true`, used for the `<group>_init` function.
+    ///
+    /// Returns the number of prewarmed caches whose process was found.
+    pub fn init_group(caches: &[&'static dyn CachedLookupDyn]) -> usize {
+        caches
+            .iter()
+            .filter(|cache| cache.is_prewarm())
+            .filter(|cache| {
+                let found = cache.warm();
+                #[cfg(feature = "tracing")]
+                if !found {
+                    tracing::warn!(process_name = cache.name(), "prewarmed process not found");
+                }
+                found
+            })
+            .count()
+    }
+
+    /// Prepares a cache declared with the cached_process! macro,
+    /// setting its process if it was seeded with [`crate::seed_caches`].
+    ///
+    /// Computed names are checked to not be empty in debug builds, as empty literal names are rejected at compile time.
+    pub fn prepare<T: Clone + 'static>(
+        cache: CachedProcess<'static, T>,
+    ) -> CachedProcess<'static, T> {
+        debug_assert!(
+            cache.is_unnamed() || !cache.process_name().is_empty(),
+            "process name of a cached process must not be empty"
+        );
+        SEEDS.with(|seeds| {
+            for seeds in seeds.borrow().iter() {
+                seed_cache(&cache, &**seeds);
+            }
+        });
+        cache
+    }
+
+    /// Returns true if `name` is the stringified empty string literal.
+    pub const fn is_empty_literal(name: &str) -> bool {
+        matches!(name.as_bytes(), b"\"\"")
+    }
+
+    /// Returns true if `a` and `b` are the same stringified string literal.
+    pub const fn is_same_literal(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() || a.is_empty() || a[0] != b'"' {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+lunatic::process_local! {
+    static REGISTRY: RefCell<Vec<&'static dyn RegisteredCache>> = RefCell::new(Vec::new());
+    /// Processes passed to [`seed_caches`], by process name.
+    static SEEDS: RefCell<Vec<Box<dyn Any>>> = RefCell::new(Vec::new());
+}
+
+/// Type erased access to caches declared with the cached_process! macro.
+trait RegisteredCache {
+    fn reset(&'static self);
+
+    fn reset_if_linked(&'static self, tag: Tag) -> bool;
+
+    fn reset_matching(&'static self, prefix: &str) -> usize;
+
+    fn seed(&'static self, seeds: &dyn Any) -> bool;
+
+    fn prewarm(&'static self) -> usize;
+}
+
+impl<T: Clone + 'static> RegisteredCache for ProcessLocal<CachedProcess<'static, T>>
+where
+    CachedProcess<'static, T>: CachedLookup<'static, T>,
+{
+    fn reset(&'static self) {
+        self.with(CachedProcess::reset);
+    }
+
+    fn reset_if_linked(&'static self, tag: Tag) -> bool {
+        self.with(|cache| cache.reset_if_linked(tag))
+    }
+
+    fn reset_matching(&'static self, prefix: &str) -> usize {
+        self.with(|cache| reset_if_matching(cache, prefix))
+    }
+
+    fn seed(&'static self, seeds: &dyn Any) -> bool {
+        self.with(|cache| seed_cache(cache, seeds))
+    }
+
+    fn prewarm(&'static self) -> usize {
+        self.with(|cache| usize::from(prewarm_cache(cache)))
+    }
+}
+
+impl<T: Clone + 'static, const N: usize> RegisteredCache
+    for ProcessLocal<[CachedProcess<'static, T>; N]>
+where
+    CachedProcess<'static, T>: CachedLookup<'static, T>,
+{
+    fn reset(&'static self) {
+        self.with(|caches| caches.iter().for_each(CachedProcess::reset));
+    }
+
+    fn reset_if_linked(&'static self, tag: Tag) -> bool {
+        self.with(|caches| caches.iter().any(|cache| cache.reset_if_linked(tag)))
+    }
+
+    fn reset_matching(&'static self, prefix: &str) -> usize {
+        self.with(|caches| {
+            caches
+                .iter()
+                .map(|cache| reset_if_matching(cache, prefix))
+                .sum()
+        })
+    }
+
+    fn seed(&'static self, seeds: &dyn Any) -> bool {
+        self.with(|caches| {
+            caches
+                .iter()
+                .filter(|cache| seed_cache(cache, seeds))
+                .count()
+                > 0
+        })
+    }
+
+    fn prewarm(&'static self) -> usize {
+        self.with(|caches| caches.iter().filter(|cache| prewarm_cache(cache)).count())
+    }
+}
+
+/// Looks up the cache if it was declared with `prewarm: true`, returning whether its process was found.
+fn prewarm_cache<T>(cache: &'static CachedProcess<'static, T>) -> bool
+where
+    CachedProcess<'static, T>: CachedLookup<'static, T>,
+{
+    if !cache.is_prewarm() {
+        return false;
+    }
+    let found = cache.get().is_some();
+    #[cfg(feature = "tracing")]
+    if !found {
+        tracing::warn!(
+            process_name = cache.process_name(),
+            "prewarmed process not found"
+        );
+    }
+    found
+}
+
+/// Resets the cache if its process name starts with `prefix`, returning the number of caches reset.
+fn reset_if_matching<T>(cache: &CachedProcess<T>, prefix: &str) -> usize {
+    if cache.process_name().starts_with(prefix) {
+        cache.reset();
+        1
+    } else {
+        0
+    }
+}
+
+/// Sets the cached process if `seeds` holds processes of the cache's type, including one under its process name.
+fn seed_cache<T: Clone + 'static>(cache: &CachedProcess<T>, seeds: &dyn Any) -> bool {
+    let process = seeds
+        .downcast_ref::<HashMap<String, T>>()
+        .and_then(|seeds| seeds.get(cache.process_name()));
+    match process {
+        Some(process) => {
+            cache.set(process.duplicate());
+            true
+        }
+        None => false,
+    }
+}
+
+/// Resets the cache declared with the [`cached_process!`] macro which was linked with `tag` by [`CachedLookup::get_linked`],
+/// returning whether a cache was reset.
+///
+/// This should be called with the tag of a [`lunatic::MailboxResult::LinkDied`] message.
+pub fn reset_linked(tag: Tag) -> bool {
+    REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .iter()
+            .any(|cache| cache.reset_if_linked(tag))
+    })
+}
+
+/// Resets every cache declared with the [`cached_process!`] macro which has been used in the current process.
+///
+/// This is useful for avoiding cached lookups leaking between test cases.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, reset_all, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "foo";
+///     static BAR: Process<()> = "bar";
+/// }
+///
+/// FOO.get();
+/// BAR.get();
+///
+/// reset_all();
+/// assert!(!FOO.with(|process| process.is_looked_up()));
+/// assert!(!BAR.with(|process| process.is_looked_up()));
+/// ```
+pub fn reset_all() {
+    REGISTRY.with(|registry| {
+        for cache in registry.borrow().iter() {
+            cache.reset();
+        }
+    });
+}
+
+/// Resets every cache declared with the [`cached_process!`] macro whose process name starts with `prefix`,
+/// returning the number of caches which were reset.
+///
+/// Only caches which have been used in the current process are reset. This is useful for invalidating
+/// the caches of a tenant when it's torn down, without resetting unrelated caches.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, reset_matching, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "tenant-42-foo";
+///     static BAR: Process<()> = "tenant-43-bar";
+/// }
+///
+/// FOO.get();
+/// BAR.get();
+///
+/// assert_eq!(reset_matching("tenant-42-"), 1);
+/// assert!(!FOO.with(|process| process.is_looked_up()));
+/// assert!(BAR.with(|process| process.is_looked_up()));
+/// ```
+pub fn reset_matching(prefix: &str) -> usize {
+    REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .iter()
+            .map(|cache| cache.reset_matching(prefix))
+            .sum()
+    })
+}
+
+/// Sets the process of every cache declared with the [`cached_process!`] macro whose process name is in `processes`.
+///
+/// Caches which haven't been used yet in the current process are set when they're first used,
+/// so this can be called once when setting up a test. Returns the number of already used caches which were set.
+/// Only caches holding the same type of process are set, so each process type needs its own call.
+///
+/// # Example
+///
+/// ```
+/// use lunatic::Process;
+/// use lunatic_cached_process::{cached_process, seed_caches, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "foo";
+///     static BAR: Process<()> = "bar";
+/// }
+///
+/// let process: Process<()> = Process::this();
+/// seed_caches([("foo", process), ("bar", process)]);
+/// assert_eq!(FOO.get(), Some(process));
+/// assert_eq!(BAR.get(), Some(process));
+/// ```
+pub fn seed_caches<N, T>(processes: impl IntoIterator<Item = (N, T)>) -> usize
+where
+    N: Into<String>,
+    T: Clone + 'static,
+{
+    let seeds: HashMap<String, T> = processes
+        .into_iter()
+        .map(|(name, process)| (name.into(), process))
+        .collect();
+    let seeded = REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .iter()
+            .filter(|cache| cache.seed(&seeds))
+            .count()
+    });
+    SEEDS.with(|all_seeds| all_seeds.borrow_mut().push(Box::new(seeds)));
+    seeded
+}
+
+/// Object safe access to process local caches of any process type, used by [`warm`] and [`present_names`].
+///
+/// This is implemented for caches declared with the [`cached_process!`] macro.
+pub trait CachedLookupDyn {
+    /// Returns the process name.
+    fn name(&'static self) -> &'static str;
+
+    /// Looks up the process like [`CachedLookup::get`], returning whether it was found.
+    fn warm(&'static self) -> bool;
+
+    /// Returns true if the process was looked up and found, without looking it up.
+    fn is_present(&'static self) -> bool;
+
+    /// Returns whether the cache holds a [`Process`] or a [`ProcessRef`], like [`CachedLookup::resolver_kind`].
+    fn kind(&'static self) -> ResolverKind;
+
+    /// Returns true if the cache is looked up by [`init`], like [`CachedProcess::is_prewarm`].
+    fn is_prewarm(&'static self) -> bool;
+}
+
+impl<T> CachedLookupDyn for ProcessLocal<CachedProcess<'static, T>>
+where
+    CachedProcess<'static, T>: CachedLookup<'static, T>,
+{
+    fn name(&'static self) -> &'static str {
+        self.with(|cache| cache.process_name())
+    }
+
+    fn warm(&'static self) -> bool {
+        self.get().is_some()
+    }
+
+    fn is_present(&'static self) -> bool {
+        self.with(|cache| cache.is_present())
+    }
+
+    fn kind(&'static self) -> ResolverKind {
+        self.resolver_kind()
+    }
+
+    fn is_prewarm(&'static self) -> bool {
+        self.with(CachedProcess::is_prewarm)
+    }
+}
+
+/// Looks up each of the caches, so the first real lookup doesn't need to lookup the process from the lunatic runtime.
+///
+/// Returns the number of caches whose process was found.
+/// This is typically called once at startup, after the processes have been spawned.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, warm, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "foo";
+///     static BAR: ProcessRef<()> = "bar";
+/// }
+///
+/// assert_eq!(warm(&[&FOO, &BAR]), 0);
+/// assert!(FOO.with(|process| process.is_looked_up()));
+/// ```
+pub fn warm(caches: &[&'static dyn CachedLookupDyn]) -> usize {
+    caches
+        .iter()
+        .filter(|cache| {
+            let found = cache.warm();
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                process_name = cache.name(),
+                present = found,
+                "warmed process cache"
+            );
+            found
+        })
+        .count()
+}
+
+/// Looks up each of the caches declared with `prewarm: true` with the [`cached_process!`] macro,
+/// which should be called once at process startup.
+///
+/// Returns the number of prewarmed caches whose process was found. Caches which weren't found
+/// are logged as a warning with the `tracing` feature. Like [`reset_all`], only caches which have been used
+/// in the current process are looked up. Group functions declared with the [`cached_process!`] macro
+/// also generate a `<group>_init` function, which initializes every cache of the group, used or not.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{cached_process, init, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "foo", { prewarm: true };
+///     static BAR: Process<()> = "bar";
+/// }
+///
+/// FOO.with(|process| assert!(process.is_prewarm()));
+/// BAR.with(|process| assert!(!process.is_prewarm()));
+///
+/// assert_eq!(init(), 0);
+/// assert!(FOO.with(|process| process.is_looked_up()));
+/// assert!(!BAR.with(|process| process.is_looked_up()));
+/// ```
+pub fn init() -> usize {
+    // Collected first, as a resolver may use a cache for the first time, registering it
+    let caches: Vec<_> = REGISTRY.with(|registry| registry.borrow().clone());
+    caches.iter().map(|cache| cache.prewarm()).sum()
+}
+
+/// Returns the names of the caches which currently hold a process, without looking up any of the caches.
+///
+/// This is useful for reporting which dependencies are up, such as in a health check.
+/// Group functions declared with the [`cached_process!`] macro also generate a `<group>_present_names` function.
+///
+/// # Example
+///
+/// ```
+/// use lunatic::Process;
+/// use lunatic_cached_process::{cached_process, present_names, CachedLookup};
+///
+/// cached_process! {
+///     static FOO: Process<()> = "foo";
+///     static BAR: Process<()> = "bar";
+/// }
+///
+/// FOO.set(Process::this());
+/// assert_eq!(present_names(&[&FOO, &BAR]), ["foo"]);
+/// ```
+pub fn present_names(caches: &[&'static dyn CachedLookupDyn]) -> Vec<&'static str> {
+    caches
+        .iter()
+        .filter(|cache| cache.is_present())
+        .map(|cache| cache.name())
+        .collect()
+}
+
+/// Process name of a [`CachedProcess`], which can be set once for caches constructed without a name.
+#[derive(Clone, Debug)]
+struct ProcessName<'a> {
+    declared: Option<Cow<'a, str>>,
+    assigned: OnceCell<String>,
+}
+
+impl<'a> ProcessName<'a> {
+    const fn new(declared: Option<Cow<'a, str>>) -> Self {
+        ProcessName {
+            declared,
+            assigned: OnceCell::new(),
+        }
+    }
+
+    fn get(&self) -> Option<&str> {
+        self.declared
+            .as_deref()
+            .or(self.assigned.get().map(String::as_str))
+    }
+
+    fn set(&self, name: String) -> bool {
+        self.declared.is_none() && self.assigned.set(name).is_ok()
+    }
+}
+
+impl PartialEq for ProcessName<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for ProcessName<'_> {}
+
+impl Serialize for ProcessName<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProcessName<'_> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = Option::<String>::deserialize(deserializer)?;
+        Ok(ProcessName::new(name.map(Cow::Owned)))
+    }
+}
+
+impl<T> LookupState<T> {
+    fn cache_state(&self) -> CacheState {
+        match self {
+            LookupState::NotLookedUp => CacheState::NotLookedUp,
+            LookupState::NotPresent(..) => CacheState::Absent,
+            LookupState::Present(..) => CacheState::Present,
+        }
+    }
+
+    fn process(&self) -> Option<&T> {
+        match self {
+            LookupState::Present(process, _, _) => Some(process),
+            LookupState::NotLookedUp | LookupState::NotPresent(..) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+enum LookupState<T> {
+    #[default]
+    NotLookedUp,
+    /// When the process was looked up, and the number of consecutive failed lookups before it.
+    NotPresent(#[serde(skip, default = "Instant::now")] Instant, u32),
+    /// The cached process, when it was resolved, and the index of the name it was found under.
+    Present(
+        T,
+        #[serde(skip, default = "Instant::now")] Instant,
+        Option<usize>,
+    ),
+}
+
+/// Returns the lookup state of a deserialized [`CachedProcess`].
+fn not_looked_up<T>() -> RefCell<LookupState<T>> {
+    RefCell::new(LookupState::NotLookedUp)
+}
+
+/// Deserializes the lookup state of a [`CachedProcess`], discarding it so each process performs its own lookup.
+#[cfg(not(feature = "serialize-name-only"))]
+fn deserialize_not_looked_up<'de, D, T>(
+    deserializer: D,
+) -> Result<RefCell<LookupState<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    LookupState::<T>::deserialize(deserializer)?;
+    Ok(not_looked_up())
+}
+
+/// Metadata cached by [`CachedProcess::get_with_meta`], stored as `Send` so it doesn't change the auto traits of the cache.
+#[derive(Debug)]
+struct Meta {
+    value: Box<dyn Any + Send>,
+    clone: fn(&(dyn Any + Send)) -> Box<dyn Any + Send>,
+}
+
+impl Meta {
+    fn new<M: Clone + Send + 'static>(value: M) -> Self {
+        Meta {
+            value: Box::new(value),
+            clone: |value| Box::new(value.downcast_ref::<M>().unwrap().clone()),
+        }
+    }
+}
+
+impl Clone for Meta {
+    fn clone(&self) -> Self {
+        Meta {
+            value: (self.clone)(&*self.value),
+            clone: self.clone,
+        }
+    }
+}
+
+/// Handle stored in a [`CachedProcess`], duplicated whenever it's returned from the cache.
+trait CacheableHandle {
+    /// Returns a duplicate of the handle.
+    fn duplicate(&self) -> Self;
+}
+
+impl<T: Clone> CacheableHandle for T {
+    #[inline]
+    fn duplicate(&self) -> Self {
+        // TODO: Replace clone with copy when lunatic gets a new version where `ProcessRef` is `Copy`.
+        self.clone()
+    }
+}
+
+#[inline]
+fn lookup<F, T>(proc: &CachedProcess<T>, f: F) -> Result<T, LookupError>
+where
+    F: Fn(&str) -> Option<T>,
+    T: Clone,
+{
+    try_lookup(proc, |name| Ok(f(name)))
+}
+
+/// Looks up the process like [`lookup`], with a resolver which can fail.
+///
+/// If the resolver fails, its error is returned and the cache is left untouched.
+#[inline]
+fn try_lookup<F, T>(proc: &CachedProcess<T>, f: F) -> Result<T, LookupError>
+where
+    F: Fn(&str) -> Result<Option<T>, LookupError>,
+    T: Clone,
+{
+    if proc.is_unnamed() {
+        return Err(LookupError::Unnamed);
+    }
+    if cfg!(feature = "no-cache") {
+        return try_resolve(proc, f);
+    }
+
+    let proc_ref = proc.lookup_state.borrow();
+    match &*proc_ref {
+        LookupState::NotLookedUp | LookupState::NotPresent(..) if proc.disabled.get() => {
+            Err(LookupError::Disabled)
+        }
+        LookupState::NotPresent(looked_up_at, attempts)
+            if !is_expired(proc.negative_ttl_after(*attempts), looked_up_at, proc.now()) =>
+        {
+            proc.record_hit(false);
+            Err(LookupError::NotRegistered)
+        }
+        LookupState::Present(process, resolved_at, _)
+            if !is_expired(proc.ttl, resolved_at, proc.now()) =>
+        {
+            proc.record_hit(true);
+            Ok(process.duplicate())
+        }
+        LookupState::NotLookedUp | LookupState::NotPresent(..) | LookupState::Present(..) => {
+            std::mem::drop(proc_ref);
+            try_resolve(proc, f)
+        }
+    }
+}
+
+/// Looks up the process from the lunatic runtime regardless of the cached state, and caches the result.
+#[inline]
+fn resolve<F, T>(proc: &CachedProcess<T>, f: F) -> Result<T, LookupError>
+where
+    F: Fn(&str) -> Option<T>,
+    T: Clone,
+{
+    try_resolve(proc, |name| Ok(f(name)))
+}
+
+/// Looks up the process like [`resolve`], with a resolver which can fail.
+///
+/// If the resolver fails, its error is returned without caching the process as absent or counting a miss.
+#[inline]
+fn try_resolve<F, T>(proc: &CachedProcess<T>, f: F) -> Result<T, LookupError>
+where
+    F: Fn(&str) -> Result<Option<T>, LookupError>,
+    T: Clone,
+{
+    if proc.is_unnamed() {
+        return Err(LookupError::Unnamed);
+    }
+    if proc.disabled.get() {
+        return Err(LookupError::Disabled);
+    }
+
+    let Some(resolving) = ResolvingGuard::new(&proc.resolving) else {
+        return Err(LookupError::Reentrant);
+    };
+
+    let started_at = proc.now();
+    let find = || {
+        for (name_index, name) in proc.registry_names().enumerate() {
+            if let Some(process) = f(&name)? {
+                return Ok(Some((name_index, process)));
+            }
+        }
+        Ok(None)
+    };
+    let search = || {
+        for attempt in 0..=proc.retries {
+            if attempt > 0 {
+                if let Some(backoff) = proc.backoff {
+                    lunatic::sleep(backoff);
+                }
+            }
+
+            if let Some(found) = find()? {
+                return Ok(Some(found));
+            }
+        }
+        if proc.double_check {
+            // Yield so a process which is being registered can finish before it's cached as absent
+            lunatic::sleep(Duration::ZERO);
+            return find();
+        }
+        Ok(None)
+    };
+    let found = search();
+    drop(resolving);
+    let found = found?;
+    proc.record_stats(|stats| stats.misses += 1);
+    proc.last_was_hit.set(Some(false));
+    let duration = proc.now().saturating_duration_since(started_at);
+    proc.record_stats(|stats| stats.last_lookup_duration = Some(duration));
+    match found {
+        Some((name_index, process)) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                process_name = proc.process_name(),
+                resolved_name = proc.names().nth(name_index),
+                present = true,
+                "process cache miss"
+            );
+            if !cfg!(feature = "no-cache") {
+                proc.set_state(LookupState::Present(
+                    process.duplicate(),
+                    proc.now(),
+                    Some(name_index),
+                ));
+            }
+            Ok(process)
+        }
+        None => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                process_name = proc.process_name(),
+                present = false,
+                "process cache miss"
+            );
+            proc.record_stats(|stats| stats.absent += 1);
+            let attempts = match &*proc.lookup_state.borrow() {
+                LookupState::NotPresent(_, attempts) => attempts.saturating_add(1),
+                LookupState::NotLookedUp | LookupState::Present(..) => 0,
+            };
+            if !cfg!(feature = "no-cache") {
+                proc.set_state(LookupState::NotPresent(proc.now(), attempts));
+            }
+            Err(LookupError::NotRegistered)
+        }
+    }
+}
+
+/// Looks up the process from the lunatic runtime without reading or updating the cache.
+#[inline]
+fn resolve_uncached<F, T>(proc: &CachedProcess<T>, f: F) -> Option<T>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    if proc.disabled.get() || proc.is_unnamed() {
+        return None;
+    }
+
+    let _resolving = ResolvingGuard::new(&proc.resolving)?;
+    proc.registry_names().find_map(|name| f(&name))
+}
+
+/// Marks a cache as resolving until it's dropped, so a resolver which panics doesn't leave the cache marked.
+struct ResolvingGuard<'a>(&'a Cell<bool>);
+
+impl<'a> ResolvingGuard<'a> {
+    /// Marks the cache as resolving, returning `None` if it's already resolving.
+    fn new(resolving: &'a Cell<bool>) -> Option<Self> {
+        (!resolving.replace(true)).then(|| ResolvingGuard(resolving))
+    }
+}
+
+impl Drop for ResolvingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+/// Runs `get`, also returning `true` if it looked up the process rather than using the cached value.
+#[inline]
+fn lookup_fresh<T>(proc: &CachedProcess<T>, get: impl FnOnce() -> Option<T>) -> (Option<T>, bool) {
+    let misses = proc.stats.get().misses;
+    let process = get();
+    (process, proc.stats.get().misses != misses)
+}
+
+#[inline]
+fn peek<T: Clone>(proc: &CachedProcess<T>) -> Option<T> {
+    match &*proc.lookup_state.borrow() {
+        LookupState::Present(process, resolved_at, _)
+            if !is_expired(proc.ttl, resolved_at, proc.now()) =>
+        {
+            Some(process.duplicate())
+        }
+        _ => None,
+    }
+}
+
+#[inline]
+fn lookup_alive<F, T>(proc: &CachedProcess<T>, f: F) -> Result<T, LookupError>
+where
+    F: Fn(&str) -> Option<T>,
+    T: Clone,
+{
+    let process = lookup(proc, &f)?;
+    if proc.is_alive(&process) {
+        return Ok(process);
+    }
+
+    // The cached process is dead, so look it up again in case it was re-registered
+    proc.set_state(proc.reset_state());
+    if let Some(on_dead) = proc.on_dead {
+        on_dead(proc.process_name());
+    }
+    let process = lookup(proc, f)?;
+    if proc.is_alive(&process) {
+        Ok(process)
+    } else {
+        proc.set_state(proc.reset_state());
+        Err(LookupError::Dead)
+    }
+}
+
+/// Converts a process handle to another handle of the same process, such as a [`ProcessRef`] to a [`Process`],
+/// or a handle to and from its `(node_id, process_id)`.
+///
+/// lunatic doesn't expose a constructor for its handles, or the node of a [`ProcessRef`], so the handle
+/// is serialized and deserialized as the other type. This relies on every handle serializing as its
+/// node id followed by its process id, which holds for lunatic 0.12.
+#[inline]
+fn convert_handle<A: Serialize, B: DeserializeOwned>(handle: &A) -> Option<B> {
+    bincode::deserialize(&bincode::serialize(handle).ok()?).ok()
+}
+
+/// Returns true if a lookup made at `since` has outlived the `ttl` at `now`.
+#[inline]
+fn is_expired(ttl: Option<Duration>, since: &Instant, now: Instant) -> bool {
+    ttl.is_some_and(|ttl| now.saturating_duration_since(*since) >= ttl)
 }
@@ -0,0 +1,539 @@
+#[cfg(feature = "cached")]
+use std::{borrow::Borrow, convert::Infallible};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+    ops::Deref,
+};
+
+use lunatic::{process::ProcessRef, serializer::Bincode, Process, ProcessLocal};
+
+#[cfg(feature = "cached")]
+use crate::LookupState;
+use crate::{CacheableHandle, CachedLookup, CachedProcess};
+
+pub type ShardedProcessCached<'a, K, T, S = Bincode> = ShardedCachedProcess<'a, K, Process<T, S>>;
+pub type ShardedProcessRefCached<'a, K, T> = ShardedCachedProcess<'a, K, ProcessRef<T>>;
+
+/// Process cache for processes registered under a shared prefix followed by a key, such as `"worker-0"`, `"worker-1"`.
+///
+/// Each key is looked up and cached separately.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::ShardedProcessCached;
+///
+/// let workers: ShardedProcessCached<'_, u32, ()> = ShardedProcessCached::new("worker-");
+/// assert!(workers.get(0).is_none()); // Looks up "worker-0"
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShardedCachedProcess<'a, K, T> {
+    prefix: Cow<'a, str>,
+    shards: RefCell<HashMap<K, CachedProcess<'static, T>>>,
+}
+
+impl<'a, K, T> ShardedCachedProcess<'a, K, T> {
+    /// Construct a new sharded process cache with a process name prefix.
+    pub fn new(prefix: impl Into<Cow<'a, str>>) -> Self {
+        ShardedCachedProcess {
+            prefix: prefix.into(),
+            shards: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Construct a new sharded process cache with a process name prefix, with space for at least `capacity` keys.
+    pub fn with_capacity(prefix: impl Into<Cow<'a, str>>, capacity: usize) -> Self {
+        ShardedCachedProcess {
+            prefix: prefix.into(),
+            shards: RefCell::new(HashMap::with_capacity(capacity)),
+        }
+    }
+
+    /// Returns the process name prefix.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Resets the cache of every key.
+    pub fn reset_all(&self) {
+        self.shards.borrow_mut().clear();
+    }
+}
+
+impl<'a, K, T> ShardedCachedProcess<'a, K, T>
+where
+    K: Eq + Hash + Display,
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    /// Looks up the process registered under the prefix followed by `key`, and caches the result.
+    /// Subsequent calls with the same key will use the cached value.
+    pub fn get(&self, key: K) -> Option<T> {
+        let mut shards = self.shards.borrow_mut();
+        let cache = shards
+            .entry(key)
+            .or_insert_with_key(|key| CachedProcess::new(format!("{}{key}", self.prefix)));
+        cache.get()
+    }
+
+    /// Resets the cache of `key`, causing the next call to [`ShardedCachedProcess::get`] to lookup the process again.
+    pub fn reset(&self, key: &K) {
+        self.shards.borrow_mut().remove(key);
+    }
+}
+
+pub type DynamicProcessCached<T, S = Bincode> = DynamicCachedProcess<Process<T, S>>;
+pub type DynamicProcessRefCached<T> = DynamicCachedProcess<ProcessRef<T>>;
+
+/// Process cache for process names only known at runtime, such as plugin names.
+///
+/// Each name is looked up and cached separately when it's first requested.
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::DynamicProcessCached;
+///
+/// let plugins: DynamicProcessCached<()> = DynamicProcessCached::new();
+/// assert!(plugins.get("plugin-foo").is_none()); // Looks up "plugin-foo"
+/// assert_eq!(plugins.len(), 1);
+/// ```
+///
+/// A faster hasher can be used for the names with [`DynamicCachedProcess::with_capacity_and_hasher`].
+#[derive(Clone, Debug)]
+pub struct DynamicCachedProcess<T, H = RandomState> {
+    caches: RefCell<HashMap<String, CachedProcess<'static, T>, H>>,
+}
+
+impl<T> DynamicCachedProcess<T> {
+    /// Construct a new empty dynamic process cache.
+    pub fn new() -> Self {
+        DynamicCachedProcess {
+            caches: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Construct a new empty dynamic process cache with space for at least `capacity` names,
+    /// avoiding rehashing when many names are looked up at startup.
+    pub fn with_capacity(capacity: usize) -> Self {
+        DynamicCachedProcess {
+            caches: RefCell::new(HashMap::with_capacity(capacity)),
+        }
+    }
+}
+
+impl<T> Default for DynamicCachedProcess<T> {
+    fn default() -> Self {
+        DynamicCachedProcess::new()
+    }
+}
+
+impl<T, H> DynamicCachedProcess<T, H> {
+    /// Construct a new empty dynamic process cache with space for at least `capacity` names,
+    /// using `hasher` to hash the names.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: H) -> Self {
+        DynamicCachedProcess {
+            caches: RefCell::new(HashMap::with_capacity_and_hasher(capacity, hasher)),
+        }
+    }
+
+    /// Returns the number of names which have been looked up.
+    pub fn len(&self) -> usize {
+        self.caches.borrow().len()
+    }
+
+    /// Returns true if no names have been looked up.
+    pub fn is_empty(&self) -> bool {
+        self.caches.borrow().is_empty()
+    }
+
+    /// Resets the cache of every name.
+    pub fn clear(&self) {
+        self.caches.borrow_mut().clear();
+    }
+}
+
+impl<T, H: BuildHasher> DynamicCachedProcess<T, H> {
+    /// Resets the cache of `name`, causing the next call to [`DynamicCachedProcess::get`] to lookup the process again.
+    pub fn reset(&self, name: &str) {
+        self.caches.borrow_mut().remove(name);
+    }
+}
+
+impl<T, H> DynamicCachedProcess<T, H>
+where
+    H: BuildHasher,
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    /// Looks up the process registered under `name`, and caches the result.
+    /// Subsequent calls with the same name will use the cached value.
+    pub fn get(&self, name: &str) -> Option<T> {
+        let mut caches = self.caches.borrow_mut();
+        if !caches.contains_key(name) {
+            caches.insert(name.to_string(), CachedProcess::new(name.to_string()));
+        }
+        caches[name].get()
+    }
+
+    /// Looks up the processes registered under each of `names` like [`DynamicCachedProcess::get`], in order.
+    pub fn get_many(&self, names: &[&str]) -> Vec<Option<T>> {
+        names.iter().map(|name| self.get(name)).collect()
+    }
+}
+
+/// Adapter for the [`cached::Cached`] trait, keyed by process name with the process handle as the value.
+///
+/// [`Cached::cache_get`](cached::Cached::cache_get) looks up the process of a name which is already in the cache,
+/// such as after [`Cached::cache_get_or_set_with`](cached::Cached::cache_get_or_set_with), returning `None`
+/// if the process isn't registered. [`Cached::cache_set`](cached::Cached::cache_set) caches a process for a name,
+/// preventing it from being looked up, and [`Cached::cache_remove`](cached::Cached::cache_remove) forgets the name.
+/// Hits and misses are the sum of the [`CacheStats`](crate::CacheStats) of every name.
+///
+/// # Example
+///
+/// ```
+/// use cached::Cached;
+/// use lunatic::Process;
+/// use lunatic_cached_process::DynamicProcessCached;
+///
+/// let mut plugins: DynamicProcessCached<()> = DynamicProcessCached::new();
+/// let process = *plugins.cache_get_or_set_with("plugin-foo".to_string(), Process::this);
+/// assert_eq!(plugins.cache_get("plugin-foo"), Some(&process)); // Served from the cache
+/// assert_eq!(plugins.cache_size(), 1);
+/// ```
+#[cfg(feature = "cached")]
+impl<T, H> cached::Cached<String, T> for DynamicCachedProcess<T, H>
+where
+    H: BuildHasher,
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    type Error = Infallible;
+
+    fn cache_get<Q>(&mut self, k: &Q) -> Option<&T>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lookup_mut(self.caches.get_mut().get_mut(k)?).map(|process| &*process)
+    }
+
+    fn cache_get_mut<Q>(&mut self, k: &Q) -> Option<&mut T>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lookup_mut(self.caches.get_mut().get_mut(k)?)
+    }
+
+    fn cache_set(&mut self, k: String, v: T) -> Option<T> {
+        self.caches
+            .get_mut()
+            .entry(k)
+            .or_insert_with_key(|name| CachedProcess::new(name.clone()))
+            .replace(v)
+    }
+
+    fn cache_get_or_set_with_mut<F: FnOnce() -> T>(&mut self, key: String, f: F) -> &mut T {
+        match self.cache_try_get_or_set_with_mut(key, || Ok::<_, Infallible>(f())) {
+            Ok(process) => process,
+            Err(err) => match err {},
+        }
+    }
+
+    fn cache_try_get_or_set_with_mut<F: FnOnce() -> Result<T, E>, E>(
+        &mut self,
+        key: String,
+        f: F,
+    ) -> Result<&mut T, E> {
+        let cache = self
+            .caches
+            .get_mut()
+            .entry(key)
+            .or_insert_with_key(|name| CachedProcess::new(name.clone()));
+        let process = cache.get();
+        if process.is_none() {
+            cache.set(f()?);
+        }
+        Ok(cached_mut(cache, process).expect("process was just cached"))
+    }
+
+    fn cache_remove<Q>(&mut self, k: &Q) -> Option<T>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache_remove_entry(k).map(|(_, process)| process)
+    }
+
+    /// Removes the cache of a name, returning the name and its cached process.
+    ///
+    /// A name without a cached process is removed, but returns `None` as it has no value.
+    fn cache_remove_entry<Q>(&mut self, k: &Q) -> Option<(String, T)>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (name, cache) = self.caches.get_mut().remove_entry(k)?;
+        let process = cache.peek();
+        cache.take();
+        Some((name, process?))
+    }
+
+    fn cache_delete<Q>(&mut self, k: &Q) -> bool
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.caches
+            .get_mut()
+            .remove(k)
+            .map(|cache| cache.take())
+            .is_some()
+    }
+
+    fn cache_clear(&mut self) {
+        self.clear();
+    }
+
+    fn cache_reset(&mut self) {
+        self.clear();
+    }
+
+    fn cache_size(&self) -> usize {
+        self.len()
+    }
+
+    fn cache_reset_metrics(&mut self) {
+        for cache in self.caches.get_mut().values() {
+            cache.clear_stats();
+        }
+    }
+
+    fn cache_hits(&self) -> Option<u64> {
+        Some(
+            self.caches
+                .borrow()
+                .values()
+                .map(|cache| cache.stats().hits)
+                .sum(),
+        )
+    }
+
+    fn cache_misses(&self) -> Option<u64> {
+        Some(
+            self.caches
+                .borrow()
+                .values()
+                .map(|cache| cache.stats().misses)
+                .sum(),
+        )
+    }
+}
+
+/// Looks up the process of a cache, returning a reference to the cached process.
+#[cfg(feature = "cached")]
+fn lookup_mut<'a, T>(cache: &'a mut CachedProcess<'static, T>) -> Option<&'a mut T>
+where
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    let process = cache.get()?;
+    cached_mut(cache, Some(process))
+}
+
+/// Returns a reference to the cached process of a cache, after `process` was looked up by it.
+#[cfg(feature = "cached")]
+fn cached_mut<'a, T>(
+    cache: &'a mut CachedProcess<'static, T>,
+    process: Option<T>,
+) -> Option<&'a mut T> {
+    if let Some(process) = process.filter(|_| cfg!(feature = "no-cache")) {
+        // The lookup didn't cache the process
+        cache.set(process);
+    }
+    match cache.lookup_state.get_mut() {
+        LookupState::Present(process, _, _) => Some(process),
+        LookupState::NotLookedUp | LookupState::NotPresent(..) => None,
+    }
+}
+
+/// Independent process caches for a set of names chosen at runtime, such as the dependencies of a spawned worker.
+///
+/// Unlike the [`cached_process!`](crate::cached_process) macro, the caches aren't tied to a static,
+/// so each worker can own a set of caches for its own names.
+///
+/// # Example
+///
+/// ```
+/// use lunatic::Process;
+/// use lunatic_cached_process::CacheSet;
+///
+/// let dependencies: CacheSet<Process<()>> = CacheSet::new(&["db", "queue"]);
+/// assert!(dependencies.get("db").is_none()); // Looks up "db"
+/// assert!(dependencies.get("unknown").is_none()); // Not in the set, so never looked up
+/// ```
+#[derive(Clone, Debug)]
+pub struct CacheSet<T> {
+    caches: Vec<CachedProcess<'static, T>>,
+}
+
+impl<T> CacheSet<T> {
+    /// Construct a new cache set with a cache for each of `names`.
+    ///
+    /// Duplicate names share a single cache.
+    pub fn new(names: &[&str]) -> Self {
+        let mut caches: Vec<CachedProcess<'static, T>> = Vec::with_capacity(names.len());
+        for name in names {
+            if !caches.iter().any(|cache| cache.process_name() == *name) {
+                caches.push(CachedProcess::new(name.to_string()));
+            }
+        }
+        CacheSet { caches }
+    }
+
+    /// Returns the cache of `name`, or `None` if it isn't in the set.
+    pub fn cache(&self, name: &str) -> Option<&CachedProcess<'static, T>> {
+        self.caches
+            .iter()
+            .find(|cache| cache.process_name() == name)
+    }
+
+    /// Returns an iterator over the names in the set.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.caches.iter().map(|cache| cache.process_name())
+    }
+
+    /// Returns the number of names in the set.
+    pub fn len(&self) -> usize {
+        self.caches.len()
+    }
+
+    /// Returns true if the set has no names.
+    pub fn is_empty(&self) -> bool {
+        self.caches.is_empty()
+    }
+}
+
+impl<T> CacheSet<T>
+where
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    /// Looks up the process registered under `name`, and caches the result.
+    /// Subsequent calls with the same name will use the cached value.
+    ///
+    /// Returns `None` without a lookup if `name` isn't in the set.
+    pub fn get(&self, name: &str) -> Option<T> {
+        self.cache(name)?.get()
+    }
+
+    /// Resets the cache of `name`, causing the next call to [`CacheSet::get`] to lookup the process again.
+    ///
+    /// Returns `false` if `name` isn't in the set.
+    pub fn reset(&self, name: &str) -> bool {
+        match self.cache(name) {
+            Some(cache) => {
+                cache.reset();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resets the cache of every name in the set.
+    pub fn reset_all(&self) {
+        for cache in &self.caches {
+            cache.reset();
+        }
+    }
+}
+
+/// Process cache for all processes registered under a prefix followed by an index, such as `"worker-0"`, `"worker-1"`.
+///
+/// The lunatic registry can't be listed, so processes are discovered by looking up `"{prefix}0"`, `"{prefix}1"`, etc.
+/// until a name isn't registered. The resulting processes are cached until [`CachedProcessGroup::reset`] is called.
+///
+/// # Example
+///
+/// ```
+/// use lunatic::Process;
+/// use lunatic_cached_process::CachedProcessGroup;
+///
+/// let workers: CachedProcessGroup<'_, Process<()>> = CachedProcessGroup::new("worker-");
+/// assert!(workers.get_all().is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachedProcessGroup<'a, T> {
+    prefix: Cow<'a, str>,
+    processes: RefCell<Option<Vec<T>>>,
+}
+
+impl<'a, T> CachedProcessGroup<'a, T> {
+    /// Construct a new process group cache with a process name prefix.
+    pub fn new(prefix: impl Into<Cow<'a, str>>) -> Self {
+        CachedProcessGroup {
+            prefix: prefix.into(),
+            processes: RefCell::new(None),
+        }
+    }
+
+    /// Returns the process name prefix.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Resets the cache, causing the next call to [`CachedProcessGroup::get_all`] to lookup the processes again.
+    pub fn reset(&self) {
+        *self.processes.borrow_mut() = None;
+    }
+}
+
+impl<'a, T> CachedProcessGroup<'a, T>
+where
+    T: Clone,
+    for<'b> CachedProcess<'static, T>: CachedLookup<'b, T>,
+{
+    /// Looks up every process in the group, and caches the result.
+    /// Subsequent calls will use the cached processes.
+    pub fn get_all(&self) -> Vec<T> {
+        self.processes
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                (0..)
+                    .map_while(|index| CachedProcess::new(format!("{}{index}", self.prefix)).get())
+                    .collect()
+            })
+            .iter()
+            .map(CacheableHandle::duplicate)
+            .collect()
+    }
+}
+
+/// Process caches for a fixed number of shards registered under a prefix followed by an index,
+/// such as `"worker-0"`..`"worker-7"`, declared with the [`cached_process_array!`](crate::cached_process_array) macro.
+///
+/// The array dereferences to the caches of the current process, so `WORKERS[i].get()` looks up the `i`-th shard
+/// without hashing a key.
+#[derive(Debug)]
+pub struct CachedProcessArray<T: 'static, const N: usize> {
+    caches: &'static ProcessLocal<[CachedProcess<'static, T>; N]>,
+}
+
+impl<T: 'static, const N: usize> CachedProcessArray<T, N> {
+    /// Construct a new process cache array from process local caches.
+    ///
+    /// This is used internally for the cached_process_array! macro.
+    #[doc(hidden)]
+    pub const fn new(caches: &'static ProcessLocal<[CachedProcess<'static, T>; N]>) -> Self {
+        CachedProcessArray { caches }
+    }
+}
+
+impl<T: 'static, const N: usize> Deref for CachedProcessArray<T, N> {
+    type Target = [CachedProcess<'static, T>; N];
+
+    fn deref(&self) -> &Self::Target {
+        self.caches.with(|caches| caches)
+    }
+}
@@ -0,0 +1,480 @@
+use std::{
+    any::type_name,
+    borrow::Cow,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use lunatic::{
+    process::{
+        AbstractProcess, Message, MessageHandler, ProcessRef, Request, RequestHandler, StartProcess,
+    },
+    serializer::Bincode,
+    MailboxResult, Process,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    lookup, lookup_alive, lookup_fresh, resolve, resolve_uncached, try_lookup, CacheableHandle,
+    CachedLookup, CachedProcess, LookupError, ResolverKind,
+};
+
+/// Name the cache server is registered under.
+const CACHE_SERVER_NAME: &str = "lunatic-cached-process::cache-server";
+
+/// How long to wait for the cache server to respond before falling back to a direct lookup.
+const CACHE_SERVER_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub type SharedProcessCached<'a, T, S = Bincode> = SharedCachedProcess<'a, Process<T, S>>;
+pub type SharedProcessRefCached<'a, T> = SharedCachedProcess<'a, ProcessRef<T>>;
+
+/// Process cache which shares resolved processes with other lunatic processes through a [`CacheServer`].
+///
+/// Lookups are cached locally like [`CachedProcess`], but on a miss the cache server is asked for the process
+/// before looking it up from the lunatic runtime. Processes which are looked up directly are stored in the cache server,
+/// so short lived processes can reuse lookups made by other processes.
+///
+/// If the cache server isn't running or doesn't respond, processes are looked up directly.
+/// Processes from the cache server are only used if they pass the filters of the local cache,
+/// such as [`ProcessCached::on_node`](crate::ProcessCached::on_node).
+///
+/// # Example
+///
+/// ```
+/// use lunatic_cached_process::{start_cache_server, CachedLookup, SharedProcessCached};
+///
+/// start_cache_server();
+///
+/// let cache: SharedProcessCached<'_, ()> = SharedProcessCached::new("foo");
+/// assert!(cache.get().is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedCachedProcess<'a, T> {
+    cache: CachedProcess<'a, T>,
+}
+
+impl<'a, T> SharedCachedProcess<'a, T> {
+    /// Construct a new shared process cache with a registered process name.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
+        SharedCachedProcess {
+            cache: CachedProcess::new(name),
+        }
+    }
+
+    /// Returns the local cache.
+    pub fn cache(&self) -> &CachedProcess<'a, T> {
+        &self.cache
+    }
+}
+
+impl<T, S> SharedProcessCached<'_, T, S> {
+    /// Looks up a process like [`CachedLookup::get`], giving up if the cache server doesn't respond within `timeout`.
+    ///
+    /// A lookup which times out returns `None`, leaving the cache as it was, as the process may still be registered.
+    pub fn get_with_timeout(&self, timeout: Duration) -> Option<Process<T, S>> {
+        shared_lookup_timeout(
+            &self.cache,
+            self.cache.process_resolver(),
+            |process| self.cache.accepts(process),
+            timeout,
+        )
+    }
+}
+
+impl<T> SharedProcessRefCached<'_, T> {
+    /// Looks up a process like [`CachedLookup::get`], giving up if the cache server doesn't respond within `timeout`.
+    ///
+    /// See [`SharedProcessCached::get_with_timeout`].
+    pub fn get_with_timeout(&self, timeout: Duration) -> Option<ProcessRef<T>> {
+        shared_lookup_timeout(
+            &self.cache,
+            self.cache.resolver(ProcessRef::lookup),
+            accept_any,
+            timeout,
+        )
+    }
+}
+
+impl<'a, T> From<CachedProcess<'a, T>> for SharedCachedProcess<'a, T> {
+    fn from(cache: CachedProcess<'a, T>) -> Self {
+        SharedCachedProcess { cache }
+    }
+}
+
+impl<'a, T, S> CachedLookup<'a, Process<T, S>> for SharedProcessCached<'_, T, S> {
+    #[inline]
+    fn get(&'a self) -> Option<Process<T, S>> {
+        self.try_get().ok()
+    }
+
+    #[inline]
+    fn try_get(&'a self) -> Result<Process<T, S>, LookupError> {
+        lookup(&self.cache, |name| {
+            shared_lookup(
+                &self.cache,
+                name,
+                self.cache.process_resolver(),
+                |process| self.cache.accepts(process),
+            )
+        })
+    }
+
+    #[inline]
+    fn process_name(&'a self) -> &'a str {
+        self.cache.process_name()
+    }
+
+    #[inline]
+    fn get_linked(&'a self) -> Option<Process<T, S>> {
+        let process = self.get()?;
+        self.cache.link(process.id());
+        Some(process)
+    }
+
+    #[inline]
+    fn set(&'a self, value: Process<T, S>) {
+        store(&self.cache, &value);
+        self.cache.set(value);
+    }
+
+    #[inline]
+    fn set_if_absent(&'a self, value: Process<T, S>) -> bool {
+        store_if_absent(&self.cache, value)
+    }
+
+    #[inline]
+    fn reset(&'a self) {
+        remove(&self.cache);
+        self.cache.reset();
+    }
+
+    #[inline]
+    fn refresh(&'a self) -> Option<Process<T, S>> {
+        remove(&self.cache);
+        resolve(&self.cache, |name| {
+            shared_lookup(
+                &self.cache,
+                name,
+                self.cache.process_resolver(),
+                |process| self.cache.accepts(process),
+            )
+        })
+        .ok()
+    }
+
+    #[inline]
+    fn get_fresh(&'a self) -> (Option<Process<T, S>>, bool) {
+        lookup_fresh(&self.cache, || self.get())
+    }
+
+    #[inline]
+    fn try_get_or_reset_if_dead(&'a self) -> Result<Process<T, S>, LookupError> {
+        shared_lookup_alive(&self.cache, self.cache.process_resolver(), |process| {
+            self.cache.accepts(process)
+        })
+    }
+
+    #[inline]
+    fn lookup_uncached(&'a self) -> Option<Process<T, S>> {
+        resolve_uncached(&self.cache, self.cache.process_resolver())
+    }
+
+    #[inline]
+    fn peek(&'a self) -> Option<Process<T, S>> {
+        CachedLookup::peek(&self.cache)
+    }
+
+    #[inline]
+    fn resolver_kind(&'a self) -> ResolverKind {
+        ResolverKind::Process
+    }
+
+    #[inline]
+    fn now(&'a self) -> Instant {
+        self.cache.now()
+    }
+}
+
+impl<'a, T> CachedLookup<'a, ProcessRef<T>> for SharedProcessRefCached<'_, T> {
+    #[inline]
+    fn get(&'a self) -> Option<ProcessRef<T>> {
+        self.try_get().ok()
+    }
+
+    #[inline]
+    fn try_get(&'a self) -> Result<ProcessRef<T>, LookupError> {
+        lookup(&self.cache, |name| {
+            shared_lookup(
+                &self.cache,
+                name,
+                self.cache.resolver(ProcessRef::lookup),
+                accept_any,
+            )
+        })
+    }
+
+    #[inline]
+    fn process_name(&'a self) -> &'a str {
+        self.cache.process_name()
+    }
+
+    #[inline]
+    fn get_linked(&'a self) -> Option<ProcessRef<T>> {
+        let process = self.get()?;
+        self.cache.link(process.id());
+        Some(process)
+    }
+
+    #[inline]
+    fn set(&'a self, value: ProcessRef<T>) {
+        store(&self.cache, &value);
+        self.cache.set(value);
+    }
+
+    #[inline]
+    fn set_if_absent(&'a self, value: ProcessRef<T>) -> bool {
+        store_if_absent(&self.cache, value)
+    }
+
+    #[inline]
+    fn reset(&'a self) {
+        remove(&self.cache);
+        self.cache.reset();
+    }
+
+    #[inline]
+    fn refresh(&'a self) -> Option<ProcessRef<T>> {
+        remove(&self.cache);
+        resolve(&self.cache, |name| {
+            shared_lookup(
+                &self.cache,
+                name,
+                self.cache.resolver(ProcessRef::lookup),
+                accept_any,
+            )
+        })
+        .ok()
+    }
+
+    #[inline]
+    fn get_fresh(&'a self) -> (Option<ProcessRef<T>>, bool) {
+        lookup_fresh(&self.cache, || self.get())
+    }
+
+    #[inline]
+    fn try_get_or_reset_if_dead(&'a self) -> Result<ProcessRef<T>, LookupError> {
+        shared_lookup_alive(
+            &self.cache,
+            self.cache.resolver(ProcessRef::lookup),
+            accept_any,
+        )
+    }
+
+    #[inline]
+    fn lookup_uncached(&'a self) -> Option<ProcessRef<T>> {
+        resolve_uncached(&self.cache, self.cache.resolver(ProcessRef::lookup))
+    }
+
+    #[inline]
+    fn peek(&'a self) -> Option<ProcessRef<T>> {
+        CachedLookup::peek(&self.cache)
+    }
+
+    #[inline]
+    fn resolver_kind(&'a self) -> ResolverKind {
+        ResolverKind::ProcessRef
+    }
+
+    #[inline]
+    fn now(&'a self) -> Instant {
+        self.cache.now()
+    }
+}
+
+/// Process holding processes resolved by [`SharedCachedProcess`] caches, shared between lunatic processes.
+///
+/// Processes are stored by their name and type, and are kept until a [`SharedCachedProcess`] is reset.
+pub struct CacheServer {
+    processes: HashMap<String, Vec<u8>>,
+}
+
+impl AbstractProcess for CacheServer {
+    type Arg = ();
+    type State = Self;
+
+    fn init(_this: ProcessRef<Self>, _arg: Self::Arg) -> Self::State {
+        CacheServer {
+            processes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Get(String);
+
+#[derive(Serialize, Deserialize)]
+struct Store(String, Vec<u8>);
+
+#[derive(Serialize, Deserialize)]
+struct Remove(String);
+
+impl RequestHandler<Get> for CacheServer {
+    type Response = Option<Vec<u8>>;
+
+    fn handle(state: &mut Self::State, Get(key): Get) -> Self::Response {
+        state.processes.get(&key).cloned()
+    }
+}
+
+impl MessageHandler<Store> for CacheServer {
+    fn handle(state: &mut Self::State, Store(key, process): Store) {
+        state.processes.insert(key, process);
+    }
+}
+
+impl MessageHandler<Remove> for CacheServer {
+    fn handle(state: &mut Self::State, Remove(key): Remove) {
+        state.processes.remove(&key);
+    }
+}
+
+/// Starts the [`CacheServer`] used by [`SharedCachedProcess`] caches.
+///
+/// This should be called once, before any shared caches are used.
+pub fn start_cache_server() -> ProcessRef<CacheServer> {
+    CacheServer::start((), Some(CACHE_SERVER_NAME))
+}
+
+/// Returns the key a process is stored under in the cache server.
+///
+/// Caches with a custom resolver set with [`CachedProcess::with_resolver`] only share processes
+/// resolved by the same resolver.
+fn cache_server_key<T>(cache: &CachedProcess<T>, name: &str) -> String {
+    match cache.resolver {
+        Some(resolver) => format!("{}/{resolver:p}/{name}", type_name::<T>()),
+        None => format!("{}/{name}", type_name::<T>()),
+    }
+}
+
+/// Accepts any process from the cache server, for caches which don't filter the processes they look up.
+fn accept_any<T>(_: &T) -> bool {
+    true
+}
+
+/// Looks up a process from the cache server, falling back to `f` and storing the result in the cache server.
+///
+/// A process from the cache server is only used if it passes `accept`, the filters of the local cache.
+fn shared_lookup<T, F, A>(cache: &CachedProcess<T>, name: &str, f: F, accept: A) -> Option<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fn(&str) -> Option<T>,
+    A: Fn(&T) -> bool,
+{
+    request_process(cache, name, f, accept, CACHE_SERVER_TIMEOUT, true).unwrap_or(None)
+}
+
+/// Looks up a process like [`CachedLookup::get`], leaving the cache untouched if the cache server
+/// doesn't respond within `timeout`.
+fn shared_lookup_timeout<T, F, A>(
+    cache: &CachedProcess<T>,
+    f: F,
+    accept: A,
+    timeout: Duration,
+) -> Option<T>
+where
+    T: Clone + Serialize + DeserializeOwned,
+    F: Fn(&str) -> Option<T>,
+    A: Fn(&T) -> bool,
+{
+    try_lookup(cache, |name| {
+        request_process(cache, name, &f, &accept, timeout, false)
+    })
+    .ok()
+}
+
+/// Requests a process from the cache server, falling back to `f` and storing the result in the cache server.
+///
+/// If the cache server doesn't respond within `timeout`, the process is looked up with `f` if `fallback_on_timeout` is set.
+/// A process from the cache server which doesn't pass `accept` is looked up with `f` instead.
+fn request_process<T, F, A>(
+    cache: &CachedProcess<T>,
+    name: &str,
+    f: F,
+    accept: A,
+    timeout: Duration,
+    fallback_on_timeout: bool,
+) -> Result<Option<T>, LookupError>
+where
+    T: Serialize + DeserializeOwned,
+    F: Fn(&str) -> Option<T>,
+    A: Fn(&T) -> bool,
+{
+    let key = cache_server_key(cache, name);
+    let server = ProcessRef::<CacheServer>::lookup(CACHE_SERVER_NAME);
+    if let Some(server) = &server {
+        match server.request_timeout(Get(key.clone()), timeout) {
+            MailboxResult::Message(Some(process)) => {
+                if let Ok(process) = bincode::deserialize(&process) {
+                    if accept(&process) {
+                        return Ok(Some(process));
+                    }
+                }
+            }
+            MailboxResult::TimedOut if !fallback_on_timeout => return Err(LookupError::TimedOut),
+            _ => {}
+        }
+    }
+
+    let Some(process) = f(name) else {
+        return Ok(None);
+    };
+    if let (Some(server), Ok(bytes)) = (server, bincode::serialize(&process)) {
+        server.send(Store(key, bytes));
+    }
+    Ok(Some(process))
+}
+
+/// Looks up a process like [`lookup_alive`], removing a dead process from the cache server before looking it up again.
+fn shared_lookup_alive<T, F, A>(cache: &CachedProcess<T>, f: F, accept: A) -> Result<T, LookupError>
+where
+    T: Clone + Serialize + DeserializeOwned,
+    F: Fn(&str) -> Option<T>,
+    A: Fn(&T) -> bool,
+{
+    let process = lookup(cache, |name| shared_lookup(cache, name, &f, &accept))?;
+    if cache.is_alive(&process) {
+        return Ok(process);
+    }
+
+    remove(cache);
+    lookup_alive(cache, |name| shared_lookup(cache, name, &f, &accept))
+}
+
+/// Stores a process in the cache server under the cache's process name.
+fn store<T: Serialize>(cache: &CachedProcess<T>, process: &T) {
+    if let (Some(server), Ok(bytes)) = (
+        ProcessRef::<CacheServer>::lookup(CACHE_SERVER_NAME),
+        bincode::serialize(process),
+    ) {
+        let name = cache.registry_name(cache.process_name());
+        server.send(Store(cache_server_key(cache, &name), bytes));
+    }
+}
+
+/// Sets the cached process if it's absent, storing it in the cache server if it was set.
+fn store_if_absent<T: Clone + Serialize>(cache: &CachedProcess<T>, process: T) -> bool {
+    let stored = cache.set_if_absent(process.duplicate());
+    if stored {
+        store(cache, &process);
+    }
+    stored
+}
+
+/// Removes every name of the cache from the cache server.
+fn remove<T>(cache: &CachedProcess<T>) {
+    if let Some(server) = ProcessRef::<CacheServer>::lookup(CACHE_SERVER_NAME) {
+        for name in cache.registry_names() {
+            server.send(Remove(cache_server_key(cache, &name)));
+        }
+    }
+}
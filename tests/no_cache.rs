@@ -0,0 +1,43 @@
+#![cfg(feature = "no-cache")]
+
+use lunatic::{test, Mailbox, Process};
+use lunatic_cached_process::{
+    cached_process, CacheState, CachedLookup, CachedProcess, ProcessCached,
+};
+
+cached_process! {
+    static NO_CACHE: Process<()> = "no-cache-process";
+}
+
+fn spawn_process() -> Process<()> {
+    Process::spawn((), |_, mailbox: Mailbox<()>| loop {
+        mailbox.receive();
+    })
+}
+
+#[test]
+fn lookups_are_not_cached() {
+    assert_eq!(NO_CACHE.get(), None);
+    assert_eq!(NO_CACHE.with(CachedProcess::state), CacheState::NotLookedUp);
+
+    let first = spawn_process();
+    first.register("no-cache-process");
+    assert_eq!(NO_CACHE.get(), Some(first));
+
+    let second = spawn_process();
+    second.register("no-cache-process");
+    assert_eq!(NO_CACHE.get(), Some(second));
+    assert_eq!(NO_CACHE.with(CachedProcess::state), CacheState::NotLookedUp);
+    assert_eq!(NO_CACHE.with(CachedProcess::stats).misses, 3);
+}
+
+#[test]
+fn with_looks_up_the_process() {
+    let cache: ProcessCached<'_, ()> = CachedProcess::new("no-cache-with-process");
+    assert!(cache.with(|process| process.is_none()));
+
+    let process = spawn_process();
+    process.register("no-cache-with-process");
+    assert!(cache.with(|found| found == Some(&process)));
+    assert_eq!(cache.state(), CacheState::NotLookedUp);
+}
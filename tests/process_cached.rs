@@ -1,5 +1,27 @@
-use lunatic::{serializer::Bincode, spawn_link, test};
-use lunatic_cached_process::{cached_process, CachedLookup};
+#![cfg(not(feature = "no-cache"))]
+
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::BuildHasherDefault,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use lunatic::{
+    process::{AbstractProcess, ProcessRef, StartProcess},
+    serializer::Bincode,
+    sleep, spawn_link, test, Mailbox, MailboxResult, Process,
+};
+use lunatic_cached_process::{
+    cached_process, cached_process_array, init, reset_all, reset_linked, reset_matching,
+    start_cache_server, warm, CacheSet, CacheSnapshot, CacheState, CacheStats, CachedLookup,
+    CachedLookupDyn, CachedProcess, CachedProcessGroup, DynamicCachedProcess, DynamicProcessCached,
+    LookupError, ManualClock, ProcessCached, ProcessRefCached, Resolution, ResolutionSource,
+    ResolverKind, ShardedCachedProcess, ShardedProcessCached, SharedProcessCached,
+};
 use serde::{Deserialize, Serialize};
 
 const PROCESS_NAME: &str = "my-awesome-process";
@@ -35,3 +57,2044 @@ fn lookup() {
 
     assert!(FOO.get().is_some()); // Should still be some since its cached
 }
+
+cached_process! {
+    static LOOKED_UP: Process<Message> = "looked-up-process";
+    static LOOKED_UP_MISSING: Process<Message> = "looked-up-missing-process";
+}
+
+#[test]
+fn is_looked_up() {
+    LOOKED_UP.with(|proc| assert!(!proc.is_looked_up()));
+
+    spawn_message_process().register("looked-up-process");
+
+    assert!(LOOKED_UP.get().is_some());
+    LOOKED_UP.with(|proc| assert!(proc.is_looked_up()));
+}
+
+#[test]
+fn is_looked_up_missing() {
+    LOOKED_UP_MISSING.with(|proc| assert!(!proc.is_looked_up()));
+
+    assert!(LOOKED_UP_MISSING.get().is_none());
+    LOOKED_UP_MISSING.with(|proc| assert!(proc.is_looked_up())); // Looked up, even though it wasn't found
+}
+
+fn spawn_message_process() -> Process<Message> {
+    spawn_link!(|mailbox: Mailbox<Message>| {
+        #[allow(unreachable_code)]
+        loop {
+            let _ = mailbox.receive();
+        }
+    })
+}
+
+cached_process! {
+    static NOT_PRESENT: Process<Message> = "not-present-process";
+}
+
+#[test]
+fn is_not_present() {
+    assert!(!NOT_PRESENT.with(|proc| proc.is_not_present())); // Not looked up
+
+    assert!(NOT_PRESENT.get().is_none());
+    assert!(NOT_PRESENT.with(|proc| proc.is_not_present())); // Looked up and absent
+
+    NOT_PRESENT.reset();
+    spawn_message_process().register("not-present-process");
+
+    assert!(NOT_PRESENT.get().is_some());
+    assert!(!NOT_PRESENT.with(|proc| proc.is_not_present())); // Looked up and present
+}
+
+cached_process! {
+    static TTL: Process<Message> = "ttl-process", ttl = Duration::from_millis(50);
+}
+
+#[test]
+fn ttl_expiry() {
+    let first = spawn_message_process();
+    first.register("ttl-process");
+    assert_eq!(TTL.get(), Some(first));
+
+    let second = spawn_message_process();
+    second.register("ttl-process");
+    assert_eq!(TTL.get(), Some(first)); // Still cached
+
+    sleep(Duration::from_millis(100));
+    assert_eq!(TTL.get(), Some(second)); // Expired, so looked up again
+}
+
+cached_process! {
+    static NEGATIVE_TTL: Process<Message> = "negative-ttl-process", negative_ttl = Duration::from_millis(50);
+}
+
+#[test]
+fn negative_ttl_expiry() {
+    assert!(NEGATIVE_TTL.get().is_none());
+
+    let process = spawn_message_process();
+    process.register("negative-ttl-process");
+    assert!(NEGATIVE_TTL.get().is_none()); // Still cached as absent
+
+    sleep(Duration::from_millis(100));
+    assert_eq!(NEGATIVE_TTL.get(), Some(process)); // Expired, so looked up again
+
+    spawn_message_process().register("negative-ttl-process");
+    sleep(Duration::from_millis(100));
+    assert_eq!(NEGATIVE_TTL.get(), Some(process)); // Found processes are still cached without a ttl
+}
+
+cached_process! {
+    static NEGATIVE_BACKOFF: Process<Message> = "negative-backoff-process", {
+        negative_ttl: Duration::from_millis(50),
+        max_negative_ttl: Duration::from_millis(200),
+    };
+}
+
+#[test]
+fn negative_ttl_backoff() {
+    assert!(NEGATIVE_BACKOFF.get().is_none()); // Cached as absent for 50ms
+    sleep(Duration::from_millis(70));
+    assert!(NEGATIVE_BACKOFF.get().is_none()); // Looked up again, cached as absent for 100ms
+
+    let process = spawn_message_process();
+    process.register("negative-backoff-process");
+    sleep(Duration::from_millis(70));
+    assert!(NEGATIVE_BACKOFF.get().is_none()); // Still cached as absent
+
+    sleep(Duration::from_millis(70));
+    assert_eq!(NEGATIVE_BACKOFF.get(), Some(process));
+}
+
+#[test]
+fn manual_clock_expiry() {
+    let clock = ManualClock::new();
+    let cache: ProcessCached<'_, Message> = CachedProcess::builder("manual-clock-process")
+        .ttl(Duration::from_secs(10))
+        .clock(&clock)
+        .build();
+
+    let first = spawn_message_process();
+    first.register("manual-clock-process");
+    assert_eq!(cache.get(), Some(first));
+
+    let second = spawn_message_process();
+    second.register("manual-clock-process");
+    clock.advance(Duration::from_secs(9));
+    assert_eq!(cache.get(), Some(first)); // Still cached
+    assert_eq!(cache.resolved_age(), Some(Duration::from_secs(9)));
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(cache.get(), Some(second)); // Expired exactly at the ttl
+}
+
+static DEAD_PROCESS_ID: AtomicU64 = AtomicU64::new(u64::MAX);
+
+lunatic::process_local! {
+    static LIVENESS: ProcessCached<'static, Message> = CachedProcess::new("liveness-process")
+        .with_liveness_check(|process| process.id() != DEAD_PROCESS_ID.load(Ordering::Relaxed));
+}
+
+#[test]
+fn get_or_reset_if_dead() {
+    let first = spawn_message_process();
+    first.register("liveness-process");
+    assert_eq!(LIVENESS.get_or_reset_if_dead(), Some(first));
+
+    let second = spawn_message_process();
+    second.register("liveness-process");
+    DEAD_PROCESS_ID.store(first.id(), Ordering::Relaxed);
+    assert_eq!(LIVENESS.get_or_reset_if_dead(), Some(second)); // Dead process is replaced by the new registration
+
+    DEAD_PROCESS_ID.store(second.id(), Ordering::Relaxed);
+    assert_eq!(LIVENESS.try_get_or_reset_if_dead(), Err(LookupError::Dead)); // Registration still points to the dead process
+    assert!(!LIVENESS.with(|proc| proc.is_looked_up()));
+}
+
+static REPLACEMENT_ID: AtomicU64 = AtomicU64::new(u64::MAX);
+
+lunatic::process_local! {
+    static ON_DEAD: ProcessCached<'static, Message> = CachedProcess::new("on-dead-process")
+        .with_on_dead(|name| {
+            let replacement = spawn_message_process();
+            replacement.register(name);
+            REPLACEMENT_ID.store(replacement.id(), Ordering::Relaxed);
+        })
+        .with_liveness_check(|process| process.id() != DEAD_PROCESS_ID.load(Ordering::Relaxed));
+}
+
+#[test]
+fn on_dead() {
+    let first = spawn_message_process();
+    first.register("on-dead-process");
+    assert_eq!(ON_DEAD.get_or_reset_if_dead(), Some(first));
+    assert_eq!(REPLACEMENT_ID.load(Ordering::Relaxed), u64::MAX);
+
+    DEAD_PROCESS_ID.store(first.id(), Ordering::Relaxed);
+    let replacement = ON_DEAD.get_or_reset_if_dead().unwrap();
+    assert_eq!(replacement.id(), REPLACEMENT_ID.load(Ordering::Relaxed)); // Replacement spawned by the hook is found
+}
+
+#[test]
+fn set_validated() {
+    lunatic::process_local! {
+        static SET_VALIDATED: ProcessCached<'static, Message> = CachedProcess::new("set-validated-process")
+            .with_liveness_check(|process| process.id() != DEAD_PROCESS_ID.load(Ordering::Relaxed));
+    }
+
+    let dead = spawn_message_process();
+    DEAD_PROCESS_ID.store(dead.id(), Ordering::Relaxed);
+    assert!(!SET_VALIDATED.with(|cache| cache.set_validated(dead)));
+    assert!(!SET_VALIDATED.with(|cache| cache.is_looked_up()));
+
+    let injected = spawn_message_process();
+    assert!(SET_VALIDATED.with(|cache| cache.set_validated(injected)));
+    assert_eq!(SET_VALIDATED.get_or_reset_if_dead(), Some(injected));
+
+    let registered = spawn_message_process();
+    registered.register("set-validated-process");
+    DEAD_PROCESS_ID.store(injected.id(), Ordering::Relaxed);
+    assert_eq!(SET_VALIDATED.get_or_reset_if_dead(), Some(registered)); // Injected process is invalidated once dead
+}
+
+cached_process! {
+    static STATS: Process<Message> = "stats-process";
+}
+
+#[test]
+fn stats() {
+    assert!(STATS.get().is_none());
+    assert!(STATS.get().is_none());
+
+    STATS.reset();
+    spawn_message_process().register("stats-process");
+    assert!(STATS.get().is_some());
+    assert!(STATS.get().is_some());
+    assert!(STATS.get().is_some());
+
+    let stats = STATS.with(|proc| proc.stats());
+    assert_eq!(stats.hits, 3);
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.absent, 1);
+
+    STATS.with(|proc| proc.clear_stats());
+    assert_eq!(STATS.with(|proc| proc.stats()), CacheStats::default());
+    assert!(STATS.with(|proc| proc.is_looked_up())); // The cached lookup is kept
+    assert!(STATS.get().is_some());
+    assert_eq!(STATS.with(|proc| proc.stats()).hits, 1);
+}
+
+#[test]
+fn get_fresh() {
+    cached_process! {
+        static GET_FRESH: Process<Message> = "get-fresh-process";
+    }
+
+    assert_eq!(GET_FRESH.get_fresh(), (None, true));
+    assert_eq!(GET_FRESH.get_fresh(), (None, false));
+
+    let process = spawn_message_process();
+    process.register("get-fresh-process");
+    GET_FRESH.reset();
+    assert_eq!(GET_FRESH.get_fresh(), (Some(process), true));
+    assert_eq!(GET_FRESH.get_fresh(), (Some(process), false));
+
+    GET_FRESH.with(|cache| cache.disable());
+    assert_eq!(GET_FRESH.get_fresh(), (None, false)); // Disabled caches don't look up the process
+}
+
+#[test]
+fn get_with_meta() {
+    cached_process! {
+        static WITH_META: Process<Message> = "with-meta-process";
+    }
+
+    let fetches = Cell::new(0);
+    let fetch = |process: &Process<Message>| {
+        fetches.set(fetches.get() + 1);
+        process.id()
+    };
+    assert_eq!(WITH_META.with(|cache| cache.get_with_meta(fetch)), None);
+    assert_eq!(fetches.get(), 0); // Not fetched without a process
+
+    let process = spawn_message_process();
+    process.register("with-meta-process");
+    WITH_META.reset();
+    assert_eq!(
+        WITH_META.with(|cache| cache.get_with_meta(fetch)),
+        Some((process, process.id()))
+    );
+    assert_eq!(
+        WITH_META.with(|cache| cache.get_with_meta(fetch)),
+        Some((process, process.id()))
+    );
+    assert_eq!(fetches.get(), 1);
+
+    let replacement = spawn_message_process();
+    replacement.register("with-meta-process");
+    WITH_META.reset();
+    assert_eq!(
+        WITH_META.with(|cache| cache.get_with_meta(fetch)),
+        Some((replacement, replacement.id()))
+    );
+    assert_eq!(fetches.get(), 2); // Fetched again for the new process
+}
+
+cached_process! {
+    static MAP: Process<Message> = "map-process";
+}
+
+#[test]
+fn get_map() {
+    assert_eq!(
+        MAP.get_map(|_| unreachable!("process isn't registered")),
+        None::<u64>
+    );
+
+    MAP.reset();
+    let process = spawn_message_process();
+    process.register("map-process");
+    assert_eq!(MAP.get_map(|process| process.id()), Some(process.id()));
+}
+
+lunatic::process_local! {
+    static FALLBACKS: ProcessCached<'static, Message> =
+        CachedProcess::new_with_fallbacks(&["fallback-process-v2", "fallback-process-v1"]);
+}
+
+#[test]
+fn new_with_fallbacks() {
+    let v1 = spawn_message_process();
+    v1.register("fallback-process-v1");
+    assert_eq!(FALLBACKS.get(), Some(v1));
+    assert_eq!(
+        FALLBACKS.with(|proc| proc.resolved_name()),
+        Some("fallback-process-v1")
+    );
+
+    let v2 = spawn_message_process();
+    v2.register("fallback-process-v2");
+    assert_eq!(FALLBACKS.get(), Some(v1)); // Still cached
+
+    FALLBACKS.reset();
+    assert_eq!(FALLBACKS.with(|proc| proc.resolved_name()), None);
+    assert_eq!(FALLBACKS.get(), Some(v2));
+    assert_eq!(
+        FALLBACKS.with(|proc| proc.resolved_name()),
+        Some("fallback-process-v2")
+    );
+}
+
+cached_process! {
+    static SPAWN: Process<Message> = "spawn-process";
+    static SPAWN_EXISTING: Process<Message> = "spawn-existing-process";
+}
+
+#[test]
+fn get_or_spawn() {
+    let process = SPAWN.with(|proc| proc.get_or_spawn(spawn_message_process));
+    assert!(process.is_some());
+    assert_eq!(SPAWN.get(), process);
+    assert_eq!(Process::lookup("spawn-process"), process); // Registered
+
+    let cached = SPAWN.with(|proc| proc.get_or_spawn(|| unreachable!("process is cached")));
+    assert_eq!(cached, process);
+}
+
+#[test]
+fn get_or_spawn_disabled() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("spawn-disabled-process");
+    cache.disable();
+    assert!(cache
+        .get_or_spawn(|| unreachable!("cache is disabled"))
+        .is_none());
+    assert!(Process::<Message>::lookup("spawn-disabled-process").is_none());
+
+    cache.reset_if_absent();
+    assert_eq!(cache.state(), CacheState::Absent); // Still disabled
+    assert!(cache.refresh().is_none());
+    assert_eq!(cache.try_get(), Err(LookupError::Disabled));
+    assert_eq!(cache.state(), CacheState::Absent);
+}
+
+#[test]
+fn get_or_spawn_existing() {
+    assert!(SPAWN_EXISTING.get().is_none());
+
+    let existing = spawn_message_process();
+    existing.register("spawn-existing-process");
+
+    // Registered after being cached as not present
+    let process = SPAWN_EXISTING.with(|proc| proc.get_or_spawn(|| unreachable!("process exists")));
+    assert_eq!(process, Some(existing));
+}
+
+const SHARD_ID: u32 = 3;
+
+cached_process! {
+    static SHARD: Process<Message> = format!("shard-process-{SHARD_ID}");
+}
+
+#[test]
+fn computed_name() {
+    assert_eq!(
+        SHARD.with(|proc| proc.process_name().to_string()),
+        "shard-process-3"
+    );
+
+    let process = spawn_message_process();
+    process.register("shard-process-3");
+    assert_eq!(SHARD.get(), Some(process));
+}
+
+#[test]
+fn owned_name() {
+    let name = String::from("owned-name-process");
+    let cache: ProcessCached<'static, Message> = CachedProcess::new(name);
+    assert_eq!(cache.process_name(), "owned-name-process");
+
+    let borrowed: ProcessCached<'_, Message> = CachedProcess::new("borrowed-name-process");
+    assert_eq!(borrowed.process_name(), "borrowed-name-process");
+}
+
+#[test]
+fn scoped_cache() {
+    let process = spawn_message_process();
+    process.register("scoped-cache-process");
+
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("scoped-cache-process");
+    for _ in 0..3 {
+        assert_eq!(cache.get(), Some(process));
+    }
+    assert!(cache.is_present());
+    assert_eq!(cache.stats().misses, 1);
+    assert_eq!(cache.stats().hits, 2);
+}
+
+cached_process! {
+    static PEEK: Process<Message> = "peek-process";
+}
+
+#[test]
+fn peek() {
+    let process = spawn_message_process();
+    process.register("peek-process");
+
+    assert_eq!(PEEK.peek(), None); // Not looked up yet
+    assert!(!PEEK.with(|proc| proc.is_looked_up())); // Peeking doesn't lookup
+
+    assert_eq!(PEEK.get(), Some(process));
+    assert_eq!(PEEK.peek(), Some(process));
+    assert_eq!(PEEK.with(|proc| proc.stats().misses), 1);
+}
+
+cached_process! {
+    fn dependencies();
+
+    static DEPENDENCY_A: Process<Message> = "dependency-a-process";
+    static DEPENDENCY_B: Process<Message, Bincode> = "dependency-b-process";
+    static DEPENDENCY_C: ProcessRef<Message> = "dependency-c-process";
+}
+
+#[test]
+fn group_get_all() {
+    let a = spawn_message_process();
+    a.register("dependency-a-process");
+
+    let (found_a, found_b, found_c) = dependencies();
+    assert_eq!(found_a, Some(a));
+    assert_eq!(found_b, None);
+    assert!(found_c.is_none());
+
+    assert!(DEPENDENCY_A.with(|proc| proc.is_present())); // Each entry is cached individually
+    assert!(DEPENDENCY_B.with(|proc| proc.is_not_present()));
+    assert!(DEPENDENCY_C.with(|proc| proc.is_not_present()));
+}
+
+cached_process! {
+    fn health();
+
+    static HEALTH_A: Process<Message> = "health-a-process";
+    static HEALTH_B: Process<Message> = "health-b-process";
+    static HEALTH_C: Process<Message> = "health-c-process";
+}
+
+#[test]
+fn group_present_names() {
+    let a = spawn_message_process();
+    a.register("health-a-process");
+    spawn_message_process().register("health-c-process");
+
+    assert!(health_present_names().is_empty()); // Nothing looked up yet
+
+    assert_eq!(HEALTH_A.get(), Some(a));
+    assert!(HEALTH_B.get().is_none());
+    assert_eq!(health_present_names(), ["health-a-process"]);
+    assert!(!HEALTH_C.with(|proc| proc.is_looked_up())); // Not looked up by present_names
+}
+
+cached_process! {
+    static TRY_GET: Process<Message> = "try-get-process";
+}
+
+#[test]
+fn try_get() {
+    assert_eq!(TRY_GET.try_get(), Err(LookupError::NotRegistered));
+    assert_eq!(TRY_GET.try_get(), Err(LookupError::NotRegistered)); // Cached as not registered
+
+    TRY_GET.reset();
+    let process = spawn_message_process();
+    process.register("try-get-process");
+    assert_eq!(TRY_GET.try_get(), Ok(process));
+}
+
+cached_process! {
+    static RESET_ALL_A: Process<Message> = "reset-all-a-process";
+    static RESET_ALL_B: ProcessRef<Message> = "reset-all-b-process";
+}
+
+#[test]
+fn reset_all_caches() {
+    let process = spawn_message_process();
+    process.register("reset-all-a-process");
+    assert_eq!(RESET_ALL_A.get(), Some(process));
+    assert!(RESET_ALL_B.get().is_none());
+
+    reset_all();
+    assert!(!RESET_ALL_A.with(|proc| proc.is_looked_up()));
+    assert!(!RESET_ALL_B.with(|proc| proc.is_looked_up()));
+}
+
+#[test]
+fn refresh() {
+    cached_process! {
+        static REFRESH: Process<Message> = "refresh-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("refresh-process");
+    assert_eq!(REFRESH.get(), Some(process));
+
+    let restarted = spawn_message_process();
+    restarted.register("refresh-process");
+    assert_eq!(REFRESH.get(), Some(process));
+    assert_eq!(REFRESH.refresh(), Some(restarted));
+    assert_eq!(REFRESH.get(), Some(restarted));
+    assert_eq!(REFRESH.with(|proc| proc.stats().misses), 2);
+}
+
+#[test]
+fn get_with() {
+    let resolves = AtomicU64::new(0);
+    let resolver = |name: &str| {
+        resolves.fetch_add(1, Ordering::Relaxed);
+        (name == "get-with-value").then_some(42)
+    };
+
+    let cache: CachedProcess<'_, u32> = CachedProcess::new("get-with-value");
+    assert_eq!(cache.get_with(resolver), Some(42));
+    assert_eq!(cache.get_with(resolver), Some(42));
+    assert_eq!(resolves.load(Ordering::Relaxed), 1);
+
+    let missing: CachedProcess<'_, u32> = CachedProcess::new("get-with-missing");
+    assert_eq!(missing.get_with(resolver), None);
+    assert!(missing.is_not_present());
+
+    missing.set(7);
+    assert_eq!(missing.get_with(resolver), Some(7));
+    missing.reset();
+    assert_eq!(missing.get_with(resolver), None);
+    assert_eq!(resolves.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn shared_cache() {
+    start_cache_server();
+
+    let process = spawn_message_process();
+    process.register("shared-process");
+    let cache: SharedProcessCached<'_, Message> = SharedProcessCached::new("shared-process");
+    assert_eq!(cache.get(), Some(process));
+
+    // A new cache uses the process stored in the cache server, rather than looking it up again
+    let restarted = spawn_message_process();
+    restarted.register("shared-process");
+    let other_cache: SharedProcessCached<'_, Message> = SharedProcessCached::new("shared-process");
+    assert_eq!(other_cache.get(), Some(process));
+
+    // Resetting removes the process from the cache server
+    other_cache.reset();
+    assert_eq!(other_cache.get(), Some(restarted));
+    let new_cache: SharedProcessCached<'_, Message> = SharedProcessCached::new("shared-process");
+    assert_eq!(new_cache.get(), Some(restarted));
+}
+
+#[test]
+fn shared_cache_filters() {
+    start_cache_server();
+
+    let process = spawn_message_process();
+    process.register("shared-filters-process");
+    let cache: SharedProcessCached<'_, Message> =
+        SharedProcessCached::new("shared-filters-process");
+    assert_eq!(cache.get(), Some(process));
+
+    // The process stored in the cache server isn't on the node of this cache
+    let remote: SharedProcessCached<'_, Message> = ProcessCached::on_node(
+        lunatic::distributed::node_id() + 1,
+        "shared-filters-process",
+    )
+    .into();
+    assert!(remote.get().is_none());
+
+    // Processes stored by other resolvers aren't shared
+    let resolved: SharedProcessCached<'_, Message> =
+        CachedProcess::with_resolver("shared-filters-process", |_| None).into();
+    assert!(resolved.get().is_none());
+}
+
+#[test]
+fn set_if_absent() {
+    cached_process! {
+        static SET_IF_ABSENT: Process<Message> = "set-if-absent-process";
+    }
+
+    let process = spawn_message_process();
+    let other = spawn_message_process();
+
+    assert!(SET_IF_ABSENT.set_if_absent(process)); // Not looked up
+    assert!(!SET_IF_ABSENT.set_if_absent(other)); // Present
+    assert_eq!(SET_IF_ABSENT.get(), Some(process));
+
+    SET_IF_ABSENT.reset();
+    assert!(SET_IF_ABSENT.get().is_none());
+    assert!(SET_IF_ABSENT.set_if_absent(other)); // Not present
+    assert_eq!(SET_IF_ABSENT.get(), Some(other));
+}
+
+#[test]
+fn replace() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("replace-process");
+    let process = spawn_message_process();
+    let other = spawn_message_process();
+
+    assert!(cache.get().is_none());
+    assert_eq!(cache.replace(process), None); // Not present
+    assert_eq!(cache.replace(other), Some(process));
+    assert_eq!(cache.get(), Some(other));
+}
+
+#[test]
+fn fresh_clone() {
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::with_ttl("fresh-clone-process", Duration::from_secs(5));
+    let process = spawn_message_process();
+    cache.set(process);
+
+    let clone = cache.fresh_clone();
+    assert!(!clone.is_looked_up());
+    assert_eq!(clone.stats(), CacheStats::default());
+    assert_eq!(clone.process_name(), "fresh-clone-process");
+    assert_eq!(
+        clone,
+        CachedProcess::with_ttl("fresh-clone-process", Duration::from_secs(5))
+    );
+    assert_eq!(cache.get(), Some(process)); // The original is unchanged
+}
+
+#[test]
+fn on_node() {
+    let process = spawn_message_process();
+    process.register("on-node-process");
+
+    let local: ProcessCached<'_, Message> =
+        ProcessCached::on_node(lunatic::distributed::node_id(), "on-node-process");
+    let remote: ProcessCached<'_, Message> =
+        ProcessCached::on_node(lunatic::distributed::node_id() + 1, "on-node-process");
+    assert_eq!(local.get(), Some(process));
+    assert!(remote.get().is_none()); // Registered on another node
+    assert_ne!(local, remote);
+}
+
+struct Server;
+
+impl AbstractProcess for Server {
+    type Arg = ();
+    type State = Self;
+
+    fn init(_this: ProcessRef<Self>, _arg: Self::Arg) -> Self::State {
+        Server
+    }
+}
+
+#[test]
+fn as_process() {
+    cached_process! {
+        static AS_PROCESS: ProcessRef<Server> = "as-process-server";
+    }
+
+    let server = Server::start((), Some("as-process-server"));
+    let process: Process<()> = AS_PROCESS.with(|cache| cache.as_process()).unwrap();
+    assert_eq!(process.id(), server.id());
+}
+
+#[test]
+fn get_with_node() {
+    cached_process! {
+        static WITH_NODE: Process<Message> = "with-node-process";
+        static WITH_NODE_REF: ProcessRef<Server> = "with-node-server";
+    }
+
+    let node_id = lunatic::distributed::node_id();
+    assert_eq!(WITH_NODE.with(|cache| cache.get_with_node()), None);
+
+    let process = spawn_message_process();
+    process.register("with-node-process");
+    WITH_NODE.reset();
+    assert_eq!(
+        WITH_NODE.with(|cache| cache.get_with_node()),
+        Some((process, node_id))
+    );
+
+    let server = Server::start((), Some("with-node-server"));
+    assert_eq!(
+        WITH_NODE_REF.with(|cache| cache.get_with_node()),
+        Some((server, node_id))
+    );
+}
+
+#[test]
+fn disable() {
+    cached_process! {
+        static DISABLE: Process<Message> = "disable-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("disable-process");
+
+    DISABLE.with(|cache| cache.disable());
+    assert_eq!(DISABLE.try_get(), Err(LookupError::Disabled));
+    DISABLE.reset();
+    assert_eq!(DISABLE.try_get(), Err(LookupError::Disabled)); // Still disabled after a reset
+    assert!(DISABLE.refresh().is_none());
+    assert!(DISABLE.with(|cache| cache.is_not_present()));
+
+    DISABLE.with(|cache| cache.enable());
+    assert_eq!(DISABLE.get(), Some(process));
+}
+
+#[test]
+fn get_or() {
+    cached_process! {
+        static GET_OR: Process<Message> = "get-or-process";
+    }
+
+    let stub = spawn_message_process();
+    assert_eq!(GET_OR.get_or(stub), stub);
+
+    let process = spawn_message_process();
+    process.register("get-or-process");
+    assert_eq!(GET_OR.get_or(stub), process); // The stub wasn't cached
+    assert!(!GET_OR.with(|cache| cache.is_looked_up())); // Neither was the lookup
+    assert_eq!(GET_OR.get(), Some(process));
+    assert_eq!(GET_OR.get_or(stub), process);
+
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::new("get-or-absent-process").with_negative_ttl(Duration::from_secs(60));
+    assert!(cache.get().is_none());
+    assert_eq!(cache.get_or(stub), stub);
+    assert_eq!(cache.state(), CacheState::Absent); // The cached absence is kept
+
+    cache.disable();
+    assert_eq!(cache.get_or(stub), stub);
+    assert!(cache.is_disabled());
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)] // Only the process name is hashed
+fn by_name() {
+    let process = spawn_message_process();
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("by-name-process");
+    cache.set(process);
+
+    let mut caches = HashSet::new();
+    assert!(caches.insert(cache.by_name()));
+    assert!(!caches.insert(CachedProcess::new("by-name-process").by_name())); // Same name, different state
+    assert!(caches.insert(CachedProcess::new("other-by-name-process").by_name()));
+    assert_eq!(
+        caches.get("by-name-process").and_then(|cache| cache.peek()),
+        Some(process)
+    );
+}
+
+#[test]
+fn unnamed() {
+    cached_process! {
+        static UNNAMED: Process<Message>;
+    }
+
+    let process = spawn_message_process();
+    process.register("unnamed-process");
+
+    assert_eq!(UNNAMED.try_get(), Err(LookupError::Unnamed));
+    assert!(UNNAMED.with(|cache| !cache.is_looked_up()));
+
+    assert!(UNNAMED.with(|cache| cache.set_name("unnamed-process")));
+    assert!(!UNNAMED.with(|cache| cache.set_name("other-process"))); // Names can only be set once
+    assert_eq!(UNNAMED.get(), Some(process));
+}
+
+#[test]
+fn resolver_kind() {
+    assert_eq!(FOO.resolver_kind(), ResolverKind::Process);
+    assert_eq!(BAZ.resolver_kind(), ResolverKind::ProcessRef);
+
+    let caches: [&'static dyn CachedLookupDyn; 2] = [&BAR, &BAZ];
+    let kinds = caches.map(|cache| cache.kind());
+    assert_eq!(kinds, [ResolverKind::Process, ResolverKind::ProcessRef]);
+}
+
+#[test]
+fn custom_cached_lookup() {
+    #[derive(Default)]
+    struct StubCache(Cell<Option<Process<Message>>>);
+
+    impl<'a> CachedLookup<'a, Process<Message>> for StubCache {
+        fn get(&'a self) -> Option<Process<Message>> {
+            self.0.get()
+        }
+
+        fn set(&'a self, value: Process<Message>) {
+            self.0.set(Some(value));
+        }
+
+        fn reset(&'a self) {
+            self.0.set(None);
+        }
+    }
+
+    let cache = StubCache::default();
+    assert_eq!(cache.try_get(), Err(LookupError::NotRegistered));
+    assert_eq!(cache.resolver_kind(), ResolverKind::Other);
+    assert_eq!(cache.peek(), None);
+
+    let process = spawn_message_process();
+    assert!(cache.set_if_absent(process));
+    assert!(!cache.set_if_absent(spawn_message_process()));
+    assert_eq!(cache.get_fresh(), (Some(process), true));
+    assert_eq!(cache.refresh(), None);
+}
+
+#[test]
+fn seed_caches() {
+    cached_process! {
+        static SEED_USED: Process<Message> = "seed-used-process";
+        static SEED_UNUSED: Process<Message> = "seed-unused-process";
+    }
+    cached_process! {
+        static SEED_OTHER_TYPE: Process<String> = "seed-used-process";
+    }
+
+    assert!(SEED_USED.get().is_none());
+    assert!(SEED_OTHER_TYPE.get().is_none());
+
+    let used = spawn_message_process();
+    let unused = spawn_message_process();
+    let seeded = lunatic_cached_process::seed_caches([
+        ("seed-used-process", used),
+        ("seed-unused-process", unused),
+    ]);
+    assert_eq!(seeded, 1); // Only SEED_USED has been used
+
+    assert_eq!(SEED_USED.get(), Some(used));
+    assert_eq!(SEED_UNUSED.get(), Some(unused)); // Seeded when first used
+    assert!(SEED_OTHER_TYPE.get().is_none()); // Different process type
+}
+
+#[test]
+fn with_borrowed_process() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("with-borrowed-process");
+    assert!(cache.with(|process| process.is_none()));
+
+    let process = spawn_message_process();
+    process.register("with-borrowed-process");
+    cache.reset();
+    assert_eq!(
+        cache.with(|cached| cached.map(Process::id)),
+        Some(process.id())
+    );
+    assert_eq!(cache.with(|cached| cached.copied()), Some(process)); // Served from the cache
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 1,
+            misses: 2,
+            absent: 1,
+            last_lookup_duration: cache.stats().last_lookup_duration,
+        }
+    );
+}
+
+#[test]
+fn get_with_timeout() {
+    let shared: SharedProcessCached<'_, Message> =
+        SharedProcessCached::new("get-with-timeout-process");
+    assert!(shared
+        .get_with_timeout(Duration::from_millis(100))
+        .is_none());
+
+    let process = spawn_message_process();
+    process.register("get-with-timeout-process");
+    shared.reset();
+    assert_eq!(
+        shared.get_with_timeout(Duration::from_millis(100)),
+        Some(process)
+    );
+}
+
+#[test]
+fn name_transform() {
+    cached_process! {
+        static NAME_TRANSFORM: Process<Message> = "name-transform-process", {
+            name_transform: |name| format!("{name}@v2"),
+        };
+    }
+
+    spawn_message_process().register("name-transform-process");
+    let process = spawn_message_process();
+    process.register("name-transform-process@v2");
+
+    assert_eq!(NAME_TRANSFORM.get(), Some(process));
+    assert_eq!(NAME_TRANSFORM.process_name(), "name-transform-process");
+}
+
+#[test]
+fn on_transition() {
+    static TRANSITIONS: AtomicU64 = AtomicU64::new(0);
+
+    cached_process! {
+        static ON_TRANSITION: Process<Message> = "on-transition-process", {
+            on_transition: |name, old, new| {
+                assert_eq!(name, "on-transition-process");
+                assert_ne!(old, new);
+                TRANSITIONS.fetch_add(1, Ordering::Relaxed);
+            },
+        };
+    }
+
+    assert!(ON_TRANSITION.get().is_none()); // NotLookedUp -> Absent
+    assert_eq!(ON_TRANSITION.with(CachedProcess::state), CacheState::Absent);
+    assert_eq!(TRANSITIONS.load(Ordering::Relaxed), 1);
+
+    let process = spawn_message_process();
+    ON_TRANSITION.set(process); // Absent -> Present
+    ON_TRANSITION.set(process); // No transition
+    assert_eq!(TRANSITIONS.load(Ordering::Relaxed), 2);
+
+    ON_TRANSITION.reset(); // Present -> NotLookedUp
+    ON_TRANSITION.reset(); // No transition
+    assert_eq!(TRANSITIONS.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn resolution() {
+    cached_process! {
+        static RESOLUTION: Process<Message> = "resolution-process";
+    }
+
+    assert_eq!(
+        RESOLUTION.with(CachedProcess::resolve),
+        Resolution::NotResolved
+    );
+
+    assert!(RESOLUTION.get().is_none());
+    let resolution = RESOLUTION.with(CachedProcess::resolve);
+    assert_eq!(resolution, Resolution::Absent);
+    assert!(resolution.is_resolved());
+
+    let process = spawn_message_process();
+    RESOLUTION.set(process);
+    let resolution = RESOLUTION.with(CachedProcess::resolve);
+    assert_eq!(resolution, Resolution::Present(process));
+    assert_eq!(resolution.into_option(), Some(process));
+}
+
+#[test]
+fn cached_process_array() {
+    cached_process_array! {
+        static ARRAY: [Process<Message>; 3] = "array-process-";
+    }
+
+    let process = spawn_message_process();
+    process.register("array-process-1");
+
+    assert_eq!(ARRAY.len(), 3);
+    assert_eq!(ARRAY[0].get(), None);
+    assert_eq!(ARRAY[1].get(), Some(process));
+    assert_eq!(ARRAY[2].process_name(), "array-process-2");
+
+    reset_all();
+    assert!(ARRAY.iter().all(|cache| !cache.is_looked_up()));
+}
+
+#[test]
+fn reentrant_resolver() {
+    cached_process! {
+        static REENTRANT: Process<Message> = "reentrant-process", {
+            resolver: |_| {
+                assert_eq!(REENTRANT.try_get(), Err(LookupError::Reentrant));
+                assert_eq!(REENTRANT.lookup_uncached(), None);
+                Some(Process::this())
+            },
+        };
+    }
+
+    assert_eq!(REENTRANT.get(), Some(Process::this()));
+
+    let cache: CachedProcess<'_, usize> = CachedProcess::new("reentrant-value");
+    let resolved = cache.get_with(|name| {
+        assert_eq!(cache.get_with(|name| Some(name.len() + 1)), None);
+        Some(name.len())
+    });
+    assert_eq!(resolved, Some(15));
+}
+
+#[test]
+fn dynamic_cached_process() {
+    let cache: DynamicProcessCached<Message> = DynamicProcessCached::new();
+    let process = spawn_message_process();
+    process.register("dynamic-process-foo");
+
+    assert_eq!(
+        cache.get_many(&["dynamic-process-foo", "dynamic-process-bar"]),
+        vec![Some(process), None]
+    );
+    assert_eq!(cache.len(), 2);
+
+    process.register("dynamic-process-bar");
+    assert_eq!(cache.get("dynamic-process-bar"), None); // Cached as absent
+    cache.reset("dynamic-process-bar");
+    assert_eq!(cache.get("dynamic-process-bar"), Some(process));
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn cache_set() {
+    let caches: CacheSet<Process<Message>> =
+        CacheSet::new(&["cache-set-foo", "cache-set-bar", "cache-set-foo"]);
+    assert_eq!(caches.len(), 2);
+    assert_eq!(
+        caches.names().collect::<Vec<_>>(),
+        ["cache-set-foo", "cache-set-bar"]
+    );
+
+    let process = spawn_message_process();
+    process.register("cache-set-foo");
+    process.register("cache-set-other");
+    assert_eq!(caches.get("cache-set-foo"), Some(process));
+    assert_eq!(caches.get("cache-set-bar"), None);
+    assert_eq!(caches.get("cache-set-other"), None); // Not in the set
+
+    process.register("cache-set-bar");
+    assert_eq!(caches.get("cache-set-bar"), None); // Cached as absent
+    assert!(caches.reset("cache-set-bar"));
+    assert!(!caches.reset("cache-set-other"));
+    assert_eq!(caches.get("cache-set-bar"), Some(process));
+
+    caches.reset_all();
+    assert!(caches
+        .names()
+        .all(|name| !caches.cache(name).unwrap().is_looked_up()));
+}
+
+#[cfg(feature = "cached")]
+#[test]
+fn cached_trait() {
+    use cached::Cached;
+
+    let mut cache: DynamicProcessCached<Message> = DynamicProcessCached::new();
+    assert_eq!(cache.cache_get("cached-trait-foo"), None); // Unknown names aren't looked up
+    assert_eq!(cache.cache_size(), 0);
+
+    let process = spawn_message_process();
+    process.register("cached-trait-foo");
+    assert_eq!(
+        *cache.cache_get_or_set_with("cached-trait-foo".to_string(), spawn_message_process),
+        process
+    );
+    assert_eq!(cache.cache_get("cached-trait-foo"), Some(&process));
+    assert_eq!(cache.cache_hits(), Some(1));
+    assert_eq!(cache.cache_misses(), Some(1));
+
+    let other = spawn_message_process();
+    assert_eq!(
+        cache.cache_set("cached-trait-foo".to_string(), other),
+        Some(process)
+    );
+    assert_eq!(cache.cache_remove("cached-trait-foo"), Some(other));
+    assert!(cache
+        .cache_set("cached-trait-bar".to_string(), other)
+        .is_none());
+    cache.cache_reset_metrics();
+    assert_eq!(cache.cache_hits(), Some(0));
+    cache.cache_clear();
+    assert_eq!(cache.cache_size(), 0);
+}
+
+#[test]
+fn export_import() {
+    cached_process! {
+        static EXPORTED: Process<Message> = "export-process";
+        static OTHER: Process<Message> = "export-other-process";
+    }
+    cached_process! {
+        static IMPORTED: Process<Message> = "export-process";
+    }
+
+    assert!(EXPORTED.get().is_none());
+    let snapshot = EXPORTED.with(CachedProcess::export);
+    assert_eq!(snapshot.state, CacheState::Absent);
+
+    let snapshot: CacheSnapshot =
+        bincode::deserialize(&bincode::serialize(&snapshot).unwrap()).unwrap();
+    assert!(!OTHER.with(|cache| cache.import(snapshot.clone())));
+    assert!(IMPORTED.with(|cache| cache.import(snapshot)));
+    assert_eq!(IMPORTED.with(CachedProcess::state), CacheState::Absent);
+
+    let unnamed: CachedProcess<'_, Process<Message>> = CachedProcess::unnamed();
+    EXPORTED.set(spawn_message_process());
+    assert!(unnamed.import(EXPORTED.with(CachedProcess::export)));
+    assert_eq!(unnamed.process_name(), "export-process");
+    assert_eq!(unnamed.state(), CacheState::NotLookedUp);
+}
+
+#[test]
+fn eq_process() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("eq-process");
+    let process = spawn_message_process();
+    assert!(cache != process);
+
+    cache.reset();
+    process.register("eq-process");
+    assert!(cache == process);
+    assert!(cache != Process::this());
+}
+
+#[test]
+fn resolution_order() {
+    let remote = lunatic::distributed::node_id() + 1;
+    let process = spawn_message_process();
+    process.register("resolution-order-process");
+
+    let order = [ResolutionSource::Node(remote)];
+    let cache: ProcessCached<'_, Message> =
+        ProcessCached::new("resolution-order-process").with_resolution_order(&order);
+    assert_eq!(cache.get(), None);
+
+    let order = [ResolutionSource::Node(remote), ResolutionSource::Local];
+    let cache: ProcessCached<'_, Message> = CachedProcess::builder("resolution-order-process")
+        .resolution_order(&order)
+        .build();
+    assert_eq!(cache.get(), Some(process));
+    assert_eq!(cache.resolved_source(), Some(ResolutionSource::Local));
+
+    cache.set(process);
+    assert_eq!(cache.resolved_source(), None);
+}
+
+#[test]
+fn reset_matching_prefix() {
+    cached_process! {
+        static TENANT_FOO: Process<Message> = "reset-matching-tenant-1-foo";
+        static TENANT_BAR: Process<Message> = "reset-matching-tenant-1-bar";
+        static OTHER_TENANT: Process<Message> = "reset-matching-tenant-2-foo";
+    }
+
+    TENANT_FOO.set(spawn_message_process());
+    TENANT_BAR.set(spawn_message_process());
+    OTHER_TENANT.set(spawn_message_process());
+
+    assert_eq!(reset_matching("reset-matching-tenant-1-"), 2);
+    assert!(!TENANT_FOO.with(|cache| cache.is_looked_up()));
+    assert!(!TENANT_BAR.with(|cache| cache.is_looked_up()));
+    assert!(OTHER_TENANT.with(|cache| cache.is_present()));
+}
+
+#[test]
+fn rc_cache() {
+    let cache: Rc<ProcessCached<'_, Message>> = Rc::new(CachedProcess::new("rc-process"));
+    let shared = Rc::clone(&cache);
+    let process = spawn_message_process();
+    process.register("rc-process");
+
+    assert_eq!(shared.get(), Some(process));
+    assert!(CachedLookup::peek(&cache).is_some());
+    CachedLookup::reset(&shared);
+    assert!(!cache.is_looked_up());
+}
+
+#[test]
+fn prewarm() {
+    cached_process! {
+        fn prewarm_group();
+        static PREWARM: Process<Message> = "prewarm-process", { prewarm: true };
+        static PREWARM_MISSING: Process<Message> = "prewarm-missing-process", { prewarm: true };
+        static LAZY: Process<Message> = "prewarm-lazy-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("prewarm-process");
+    process.register("prewarm-lazy-process");
+
+    assert_eq!(prewarm_group_init(), 1);
+    assert!(PREWARM.with(|cache| cache.is_present()));
+    assert!(PREWARM_MISSING.with(|cache| cache.is_looked_up()));
+    assert!(!LAZY.with(|cache| cache.is_looked_up()));
+
+    reset_all();
+    assert_eq!(init(), 1);
+    assert!(PREWARM.with(|cache| cache.is_present()));
+    assert!(PREWARM_MISSING.with(|cache| cache.is_looked_up()));
+    assert!(!LAZY.with(|cache| cache.is_looked_up()));
+}
+
+#[test]
+fn last_lookup_duration() {
+    let clock = ManualClock::new();
+    let cache: CachedProcess<'_, usize> =
+        CachedProcess::new("last-lookup-duration").with_clock(&clock);
+    assert_eq!(cache.stats().last_lookup_duration, None);
+
+    let resolver = |name: &str| {
+        clock.advance(Duration::from_millis(5));
+        Some(name.len())
+    };
+    assert_eq!(cache.get_with(resolver), Some(20));
+    assert_eq!(
+        cache.stats().last_lookup_duration,
+        Some(Duration::from_millis(5))
+    );
+
+    assert_eq!(cache.get_with(|_| panic!("expected a cache hit")), Some(20));
+    assert_eq!(
+        cache.stats().last_lookup_duration,
+        Some(Duration::from_millis(5))
+    );
+}
+
+#[test]
+fn dynamic_with_capacity() {
+    let cache: DynamicProcessCached<Message> = DynamicCachedProcess::with_capacity(16);
+    assert!(cache.is_empty());
+
+    let process = spawn_message_process();
+    process.register("dynamic-capacity-process");
+    let cache: DynamicCachedProcess<Process<Message>, BuildHasherDefault<DefaultHasher>> =
+        DynamicCachedProcess::with_capacity_and_hasher(16, BuildHasherDefault::default());
+    assert_eq!(cache.get("dynamic-capacity-process"), Some(process));
+    cache.reset("dynamic-capacity-process");
+    assert!(cache.is_empty());
+
+    let sharded: ShardedProcessCached<'_, u32, Message> =
+        ShardedCachedProcess::with_capacity("dynamic-capacity-shard-", 16);
+    assert_eq!(sharded.get(0), None);
+}
+
+#[test]
+fn display() {
+    cached_process! {
+        static DISPLAY: Process<Message> = "display-process";
+    }
+
+    assert_eq!(
+        DISPLAY.with(ToString::to_string),
+        "display-process [unresolved]"
+    );
+    assert!(DISPLAY.get().is_none());
+    assert_eq!(
+        DISPLAY.with(ToString::to_string),
+        "display-process [absent]"
+    );
+    DISPLAY.set(spawn_message_process());
+    assert_eq!(
+        DISPLAY.with(|cache| format!("{cache}")),
+        "display-process [present]"
+    );
+}
+
+#[test]
+fn by_id() {
+    let process = spawn_message_process();
+    let cache: ProcessCached<'_, Message> = ProcessCached::by_id(process.id());
+    assert_eq!(cache.process_name(), process.id().to_string());
+    assert_eq!(cache.get(), Some(process));
+
+    let cache: ProcessCached<'_, Message> =
+        ProcessCached::by_id(process.id()).with_liveness_check(|_| false);
+    assert_eq!(cache.try_get_or_reset_if_dead(), Err(LookupError::Dead));
+}
+
+#[test]
+fn take() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("take-process");
+    let process = spawn_message_process();
+    process.register("take-process");
+
+    assert_eq!(cache.take(), None);
+    assert_eq!(cache.get(), Some(process));
+    assert_eq!(cache.take(), Some(process));
+    assert!(!cache.is_looked_up());
+}
+
+#[test]
+fn into_parts() {
+    let clock = ManualClock::new();
+    let cache: ProcessCached<'_, Message> = CachedProcess::builder("into-parts-process")
+        .ttl(Duration::from_secs(5))
+        .clock(&clock)
+        .build();
+    let process = spawn_message_process();
+    cache.set(process);
+    assert_eq!(
+        cache.clone().into_parts(),
+        (Cow::Borrowed("into-parts-process"), Some(process))
+    );
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(cache.into_parts().1, None); // Expired
+
+    let unnamed: ProcessCached<'_, Message> = CachedProcess::unnamed();
+    unnamed.set_name("into-parts-assigned");
+    unnamed.set(process);
+    assert_eq!(
+        unnamed.into_parts(),
+        (
+            Cow::Owned(String::from("into-parts-assigned")),
+            Some(process)
+        )
+    );
+}
+
+mod declarations {
+    use lunatic_cached_process::cached_process;
+
+    use super::Message;
+
+    cached_process! {
+        /// A public cache.
+        pub static PUBLIC: Process<Message> = "declarations-public-process";
+        /// A crate visible cache,
+        /// documented over multiple lines.
+        #[allow(dead_code)]
+        pub(crate) static CRATE: Process<Message> = "declarations-crate-process", {
+            retries: 1,
+        };
+        #[cfg(feature = "async")]
+        pub static ASYNC: Process<Message> = "declarations-async-process";
+        #[cfg(not(feature = "async"))]
+        pub static ASYNC: ProcessRef<super::Server> = "declarations-async-process";
+        #[cfg(any())]
+        pub static REMOVED: Process<Message> = "";
+    }
+
+    cached_process! {
+        /// Returns every cache of the group.
+        pub fn group();
+        /// A cache of the group.
+        pub static GROUP_FOO: Process<Message> = "declarations-group-foo";
+        #[doc = "Another cache of the group."]
+        pub(crate) static GROUP_BAR: ProcessRef<super::Server> = "declarations-group-bar";
+    }
+
+    cached_process! {
+        handle_aliases;
+        prefix = "declarations-aliased-";
+        pub fn aliased();
+        /// A cache with a handle alias.
+        pub static ALIASED_PROCESS: Process<Message> = "process";
+        pub(crate) static ALIASED_SERVER: ProcessRef<super::Server> = "server";
+    }
+
+    cached_process! {
+        handle_aliases;
+        #[cfg(any())]
+        pub static REMOVED_ALIAS: Process<super::Missing> = "declarations-removed-alias";
+    }
+}
+
+#[test]
+fn declarations() {
+    assert_eq!(declarations::PUBLIC.get(), None);
+    assert_eq!(
+        declarations::CRATE.with(|cache| cache.process_name().to_string()),
+        "declarations-crate-process"
+    );
+    assert_eq!(
+        declarations::ASYNC.process_name(),
+        "declarations-async-process"
+    );
+    #[cfg(feature = "async")]
+    assert_eq!(declarations::ASYNC.resolver_kind(), ResolverKind::Process);
+    #[cfg(not(feature = "async"))]
+    assert_eq!(
+        declarations::ASYNC.resolver_kind(),
+        ResolverKind::ProcessRef
+    );
+    assert_eq!(declarations::group(), (None, None));
+    assert!(declarations::group_present_names().is_empty());
+
+    let aliased: (
+        Option<declarations::AliasedProcessHandle>,
+        Option<declarations::AliasedServerHandle>,
+    ) = declarations::aliased();
+    assert_eq!(aliased, (None, None));
+    assert_eq!(
+        declarations::ALIASED_PROCESS.process_name(),
+        "declarations-aliased-process"
+    );
+}
+
+#[test]
+fn and_then_cache() {
+    cached_process! {
+        static DEPENDENCY: Process<Message> = "and-then-dependency-process";
+        static DEPENDENT: ProcessRef<Server> = "and-then-dependent-process";
+    }
+
+    assert_eq!(DEPENDENCY.and_then_cache(&DEPENDENT), None);
+    assert!(!DEPENDENT.with(|cache| cache.is_looked_up()));
+
+    let process = spawn_message_process();
+    process.register("and-then-dependency-process");
+    DEPENDENCY.reset();
+    assert_eq!(DEPENDENCY.and_then_cache(&DEPENDENT), None);
+    assert!(DEPENDENT.with(|cache| cache.is_looked_up()));
+
+    let local: ProcessCached<'_, Message> = CachedProcess::new("and-then-local-process");
+    local.set(process);
+    assert_eq!(DEPENDENCY.and_then_cache(&local), Some((process, process)));
+}
+
+#[test]
+fn reset_keep_backoff() {
+    let clock = ManualClock::new();
+    let cache: CachedProcess<'_, u32> = CachedProcess::builder("reset-keep-backoff")
+        .negative_ttl(Duration::from_secs(1))
+        .max_negative_ttl(Duration::from_secs(8))
+        .clock(&clock)
+        .build();
+
+    assert_eq!(cache.get_with(|_| None), None);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(cache.get_with(|_| None), None); // Second attempt, cached for 2 seconds
+
+    clock.advance(Duration::from_secs(1));
+    cache.reset_keep_backoff();
+    assert_eq!(cache.get_with(|_| Some(1)), None); // Still backing off
+    assert_eq!(cache.stats().misses, 2);
+
+    clock.advance(Duration::from_secs(1));
+    cache.reset_keep_backoff();
+    assert_eq!(cache.get_with(|_| Some(1)), Some(1));
+    cache.reset_keep_backoff();
+    assert!(!cache.is_looked_up());
+
+    let cache: CachedProcess<'_, u32> = CachedProcess::new("reset-keep-backoff");
+    assert_eq!(cache.get_with(|_| None), None);
+    cache.reset_keep_backoff();
+    assert!(!cache.is_looked_up());
+}
+
+#[test]
+fn double_check() {
+    let calls = Cell::new(0);
+    let registered_late = |_: &str| {
+        calls.set(calls.get() + 1);
+        (calls.get() > 1).then_some(1)
+    };
+
+    let cache: CachedProcess<'_, u32> = CachedProcess::new("double-check");
+    assert_eq!(cache.get_with(registered_late), None);
+    assert_eq!(calls.get(), 1);
+
+    calls.set(0);
+    let cache: CachedProcess<'_, u32> = CachedProcess::builder("double-check")
+        .double_check(true)
+        .build();
+    assert_eq!(cache.get_with(registered_late), Some(1)); // Found by the second check
+    assert_eq!(calls.get(), 2);
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn is_stale() {
+    let clock = ManualClock::new();
+    let cache: ProcessCached<'_, Message> = CachedProcess::builder("is-stale-process")
+        .ttl(Duration::from_secs(5))
+        .clock(&clock)
+        .build();
+    assert!(!cache.is_stale());
+
+    let process = spawn_message_process();
+    process.register("is-stale-process");
+    assert_eq!(cache.get(), Some(process));
+    clock.advance(Duration::from_secs(5));
+    assert!(cache.is_stale());
+    assert_eq!(cache.stats().misses, 1); // Not looked up again
+
+    assert_eq!(cache.refresh(), Some(process));
+    assert!(!cache.is_stale());
+}
+
+cached_process! {
+    static WAIT_UNTIL_PRESENT: Process<Message> = "wait-until-present-process";
+}
+
+#[test]
+fn wait_until_present() {
+    let poll = Duration::from_millis(5);
+    assert!(WAIT_UNTIL_PRESENT
+        .wait_until_present(Duration::from_millis(20), poll)
+        .is_none());
+
+    let registered = spawn_link!(|mailbox: Mailbox<Message>| {
+        lunatic::sleep(Duration::from_millis(20));
+        Process::<Message>::this().register("wait-until-present-process");
+        #[allow(unreachable_code)]
+        loop {
+            let _ = mailbox.receive();
+        }
+    });
+    assert_eq!(
+        WAIT_UNTIL_PRESENT.wait_until_present(Duration::from_secs(5), poll),
+        Some(registered)
+    );
+
+    let misses = WAIT_UNTIL_PRESENT.with(|proc| proc.stats().misses);
+    assert_eq!(
+        WAIT_UNTIL_PRESENT.wait_until_present(Duration::ZERO, poll),
+        Some(registered)
+    );
+    assert_eq!(WAIT_UNTIL_PRESENT.with(|proc| proc.stats().misses), misses); // Found process is cached
+}
+
+thread_local! {
+    static WAIT_CLOCK: ManualClock = ManualClock::new();
+}
+
+#[test]
+fn wait_until_present_uses_clock() {
+    fn advance_clock(_: &str) -> Option<Process<Message>> {
+        WAIT_CLOCK.with(|clock| clock.advance(Duration::from_millis(10)));
+        None
+    }
+
+    WAIT_CLOCK.with(|clock| {
+        let cache: ProcessCached<'_, Message> =
+            CachedProcess::builder("wait-until-present-clock-process")
+                .resolver(advance_clock)
+                .clock(clock)
+                .build();
+        assert!(cache
+            .wait_until_present(Duration::from_millis(30), Duration::from_millis(1))
+            .is_none());
+        assert_eq!(cache.stats().misses, 4); // The initial lookup, then one per 10ms of the timeout
+    });
+}
+
+#[test]
+fn get_expect() {
+    cached_process! {
+        static GET_EXPECT: Process<Message> = "get-expect-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("get-expect-process");
+    assert_eq!(GET_EXPECT.get_expect(), process);
+}
+
+#[test]
+fn get_linked() {
+    cached_process! {
+        static GET_LINKED: Process<Message> = "get-linked-process";
+    }
+
+    let mailbox = unsafe { Mailbox::<()>::new() }.catch_link_failure();
+    let process = Process::spawn((), |_, mailbox: Mailbox<Message>| loop {
+        mailbox.receive();
+    });
+    process.register("get-linked-process");
+    assert_eq!(GET_LINKED.get_linked(), Some(process));
+    assert_eq!(GET_LINKED.get_linked(), Some(process));
+
+    process.kill();
+    match mailbox.receive() {
+        MailboxResult::LinkDied(tag) => assert!(reset_linked(tag)),
+        _ => panic!("expected linked process to die"),
+    }
+    assert!(!GET_LINKED.with(|proc| proc.is_looked_up()));
+}
+
+#[test]
+fn reset_unlinks() {
+    cached_process! {
+        static RESET_UNLINKS: Process<Message> = "reset-unlinks-process";
+    }
+
+    let mailbox = unsafe { Mailbox::<()>::new() }.catch_link_failure();
+    let process = Process::spawn((), |_, mailbox: Mailbox<Message>| loop {
+        mailbox.receive();
+    });
+    process.register("reset-unlinks-process");
+    assert_eq!(RESET_UNLINKS.get_linked(), Some(process));
+    RESET_UNLINKS.reset();
+
+    process.kill();
+    assert!(matches!(
+        mailbox.receive_timeout(Duration::from_millis(50)),
+        MailboxResult::TimedOut
+    ));
+}
+
+#[test]
+fn reset_if_present_unlinks() {
+    cached_process! {
+        static RESET_IF_PRESENT_UNLINKS: Process<Message> = "reset-if-present-unlinks-process";
+    }
+
+    let mailbox = unsafe { Mailbox::<()>::new() }.catch_link_failure();
+    let process = Process::spawn((), |_, mailbox: Mailbox<Message>| loop {
+        mailbox.receive();
+    });
+    process.register("reset-if-present-unlinks-process");
+    assert_eq!(RESET_IF_PRESENT_UNLINKS.get_linked(), Some(process));
+    RESET_IF_PRESENT_UNLINKS.with(CachedProcess::reset_if_present);
+
+    let other = spawn_message_process();
+    RESET_IF_PRESENT_UNLINKS.set(other);
+
+    process.kill();
+    if let MailboxResult::LinkDied(tag) = mailbox.receive_timeout(Duration::from_millis(50)) {
+        assert!(!reset_linked(tag));
+    }
+    assert_eq!(RESET_IF_PRESENT_UNLINKS.peek(), Some(other));
+}
+
+#[test]
+fn retries() {
+    let attempts = AtomicU64::new(0);
+    let resolver = |_: &str| {
+        let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+        (attempt == 2).then_some(attempt)
+    };
+
+    let cache: CachedProcess<'_, u64> = CachedProcess::new("retries-value")
+        .with_retries(2)
+        .with_backoff(Duration::from_millis(1));
+    assert_eq!(cache.get_with(resolver), Some(2));
+    assert_eq!(attempts.load(Ordering::Relaxed), 3);
+
+    let cache: CachedProcess<'_, u64> = CachedProcess::new("retries-value").with_retries(1);
+    assert_eq!(cache.get_with(resolver), None);
+    assert_eq!(attempts.load(Ordering::Relaxed), 5);
+    assert!(cache.is_not_present());
+    assert_eq!(cache.stats().misses, 1);
+}
+
+#[test]
+fn config_block() {
+    cached_process! {
+        static CONFIGURED: Process<Message> = "configured-process", {
+            ttl: Duration::from_millis(10),
+            retries: 1,
+        };
+    }
+
+    let process = spawn_message_process();
+    process.register("configured-process");
+    assert_eq!(CONFIGURED.get(), Some(process));
+    assert_eq!(CONFIGURED.get(), Some(process));
+    sleep(Duration::from_millis(20));
+    assert_eq!(CONFIGURED.get(), Some(process));
+    assert_eq!(CONFIGURED.with(|proc| proc.stats().misses), 2);
+}
+
+#[test]
+fn state() {
+    cached_process! {
+        static STATE: Process<Message> = "state-process";
+    }
+
+    assert_eq!(STATE.with(|proc| proc.state()), CacheState::NotLookedUp);
+    STATE.get();
+    assert_eq!(STATE.with(|proc| proc.state()), CacheState::Absent);
+    STATE.set(spawn_message_process());
+    assert_eq!(STATE.with(|proc| proc.state()), CacheState::Present);
+}
+
+#[test]
+fn new_const() {
+    lunatic::process_local! {
+        static SHARDS: [ProcessCached<'static, Message>; 2] = [
+            CachedProcess::new_const("const-shard-0"),
+            CachedProcess::new_const("const-shard-1"),
+        ];
+    }
+
+    let process = spawn_message_process();
+    process.register("const-shard-1");
+
+    assert!(SHARDS.with(|shards| shards[0].get()).is_none());
+    assert_eq!(SHARDS.with(|shards| shards[1].get()), Some(process));
+    assert_eq!(ProcessCached::<Message>::default().process_name(), "");
+}
+
+#[test]
+fn sharded() {
+    let worker_0 = spawn_message_process();
+    worker_0.register("sharded-worker-0");
+    let worker_1 = spawn_message_process();
+    worker_1.register("sharded-worker-1");
+
+    let workers: ShardedProcessCached<'_, u32, Message> =
+        ShardedProcessCached::new("sharded-worker-");
+    assert_eq!(workers.get(0), Some(worker_0));
+    assert_eq!(workers.get(1), Some(worker_1));
+    assert!(workers.get(2).is_none());
+
+    let restarted = spawn_message_process();
+    restarted.register("sharded-worker-0");
+    assert_eq!(workers.get(0), Some(worker_0));
+    workers.reset(&0);
+    assert_eq!(workers.get(0), Some(restarted));
+
+    let worker_2 = spawn_message_process();
+    worker_2.register("sharded-worker-2");
+    assert!(workers.get(2).is_none());
+    workers.reset_all();
+    assert_eq!(workers.get(2), Some(worker_2));
+}
+
+#[test]
+fn process_group() {
+    let worker_0 = spawn_message_process();
+    worker_0.register("group-worker-0");
+    let worker_1 = spawn_message_process();
+    worker_1.register("group-worker-1");
+
+    let workers: CachedProcessGroup<'_, Process<Message>> =
+        CachedProcessGroup::new("group-worker-");
+    assert_eq!(workers.get_all(), vec![worker_0, worker_1]);
+
+    let worker_2 = spawn_message_process();
+    worker_2.register("group-worker-2");
+    assert_eq!(workers.get_all(), vec![worker_0, worker_1]);
+    workers.reset();
+    assert_eq!(workers.get_all(), vec![worker_0, worker_1, worker_2]);
+}
+
+#[test]
+fn get_if() {
+    cached_process! {
+        static GET_IF: Process<Message> = "get-if-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("get-if-process");
+    assert_eq!(GET_IF.get_if(|_| unreachable!()), Some(process)); // Not cached yet
+
+    let restarted = spawn_message_process();
+    restarted.register("get-if-process");
+    assert_eq!(GET_IF.get_if(|_| true), Some(process));
+    assert_eq!(GET_IF.get_if(|cached| *cached != process), Some(restarted));
+    assert_eq!(GET_IF.with(|proc| proc.stats().misses), 2);
+}
+
+#[test]
+fn handle() {
+    cached_process! {
+        static HANDLE: Process<Message> = "handle-process";
+    }
+
+    assert!(HANDLE.handle().is_none());
+
+    let process = spawn_message_process();
+    process.register("handle-process");
+    HANDLE.reset();
+    let handle = HANDLE.handle().unwrap();
+    assert_eq!(handle.id(), process.id());
+    handle.send(Message::Hi);
+    assert_eq!(handle.into_inner(), process);
+}
+
+#[cfg(feature = "serialize-name-only")]
+#[test]
+fn serialize_name_only() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("serialize-name-only-process");
+    cache.set(spawn_message_process());
+
+    let bytes = bincode::serialize(&cache).unwrap();
+    let deserialized: ProcessCached<'_, Message> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized.process_name(), "serialize-name-only-process");
+    assert!(!deserialized.is_looked_up());
+}
+
+#[test]
+fn deserialize_not_looked_up() {
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::with_ttl("deserialize-process", Duration::from_secs(5));
+    cache.set(spawn_message_process());
+
+    let bytes = bincode::serialize(&cache).unwrap();
+    let deserialized: ProcessCached<'_, Message> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized.process_name(), "deserialize-process");
+    assert!(!deserialized.is_looked_up());
+    assert_eq!(
+        deserialized,
+        CachedProcess::with_ttl("deserialize-process", Duration::from_secs(5))
+    );
+}
+
+/// Serializes and deserializes a cache, checking the result is an unresolved cache with the same configuration.
+fn assert_roundtrip<T>(cache: &CachedProcess<'_, T>)
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+    for<'a> CachedProcess<'a, T>: PartialEq,
+{
+    let bytes = bincode::serialize(cache).unwrap();
+    let deserialized: CachedProcess<'_, T> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized.process_name(), cache.process_name());
+    assert_eq!(deserialized.state(), CacheState::NotLookedUp);
+    assert!(deserialized == cache.fresh_clone()); // Configuration is kept
+}
+
+#[test]
+fn serde_roundtrip_states() {
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::with_ttl("serde-roundtrip-process", Duration::from_secs(5));
+    assert_roundtrip(&cache);
+
+    assert!(cache.get().is_none());
+    assert_eq!(cache.state(), CacheState::Absent);
+    assert_roundtrip(&cache);
+
+    cache.set(spawn_message_process());
+    assert_eq!(cache.state(), CacheState::Present);
+    assert_roundtrip(&cache);
+
+    let server = Server::start((), Some("serde-roundtrip-server"));
+    let cache: ProcessRefCached<'_, Server> = CachedProcess::new("serde-roundtrip-server");
+    assert_roundtrip(&cache);
+    assert_eq!(cache.get(), Some(server));
+    assert_roundtrip(&cache);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn get_async() {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    cached_process! {
+        static GET_ASYNC: Process<Message> = "get-async-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("get-async-process");
+
+    let mut future = pin!(GET_ASYNC.get_async());
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(Some(process)));
+}
+
+#[test]
+fn prefix() {
+    cached_process! {
+        prefix = "prefix-test::";
+        fn prefixed();
+
+        static PREFIXED_A: Process<Message> = "a";
+        static PREFIXED_B: ProcessRef<Message> = format!("b-{}", 1);
+    }
+
+    let process = spawn_message_process();
+    process.register("prefix-test::a");
+    assert_eq!(
+        PREFIXED_A.with(|proc| proc.process_name().to_string()),
+        "prefix-test::a"
+    );
+    assert_eq!(
+        PREFIXED_B.with(|proc| proc.process_name().to_string()),
+        "prefix-test::b-1"
+    );
+    assert_eq!(prefixed(), (Some(process), None));
+}
+
+#[test]
+fn snapshot() {
+    cached_process! {
+        static SNAPSHOT: Process<Message> = "snapshot-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("snapshot-process");
+    assert_eq!(
+        SNAPSHOT.with(|proc| proc.snapshot()),
+        ("snapshot-process".to_string(), None)
+    );
+    assert!(!SNAPSHOT.with(|proc| proc.is_looked_up()));
+
+    SNAPSHOT.get();
+    assert_eq!(
+        SNAPSHOT.with(|proc| proc.snapshot()),
+        ("snapshot-process".to_string(), Some(process))
+    );
+}
+
+#[test]
+fn reset_if_absent_or_present() {
+    let absent: ProcessCached<'_, Message> = CachedProcess::new("reset-if-absent-process");
+    absent.get();
+    absent.reset_if_present();
+    assert!(absent.is_not_present());
+    absent.reset_if_absent();
+    assert!(!absent.is_looked_up());
+
+    let present: ProcessCached<'_, Message> = CachedProcess::new("reset-if-present-process");
+    present.set(spawn_message_process());
+    present.reset_if_absent();
+    assert!(present.is_present());
+    present.reset_if_present();
+    assert!(!present.is_looked_up());
+}
+
+#[test]
+fn resolved_age() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("resolved-age-process");
+    assert!(cache.resolved_age().is_none());
+    cache.get();
+    assert!(cache.resolved_age().is_none());
+
+    cache.set(spawn_message_process());
+    sleep(Duration::from_millis(10));
+    assert!(cache.resolved_age().unwrap() >= Duration::from_millis(10));
+}
+
+#[test]
+fn refers_to_same() {
+    let a: ProcessCached<'_, Message> = CachedProcess::new("refers-to-same-process");
+    let b: ProcessCached<'_, Message> = CachedProcess::new("refers-to-same-process");
+    let other: ProcessCached<'_, Message> = CachedProcess::new("refers-to-other-process");
+
+    a.get();
+    assert_ne!(a, b);
+    assert!(a.refers_to_same(&b));
+    assert!(!a.refers_to_same(&other));
+}
+
+#[test]
+fn eq_ignores_lookup_time() {
+    let clock = ManualClock::new();
+    let a: CachedProcess<'_, usize> = CachedProcess::new("eq-lookup-time").with_clock(&clock);
+    let b: CachedProcess<'_, usize> = CachedProcess::new("eq-lookup-time").with_clock(&clock);
+
+    assert_eq!(a.get_with(|_| None), None);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(b.get_with(|_| None), None);
+    assert_eq!(b.get_with(|_| None), None);
+    assert_eq!(a, b); // Both absent
+
+    a.reset();
+    b.reset();
+    assert_eq!(a.get_with(|_| Some(1)), Some(1));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(b.get_with(|_| Some(1)), Some(1));
+    assert_eq!(a, b);
+    assert_ne!(a, CachedProcess::new("eq-lookup-time").with_clock(&clock));
+}
+
+#[test]
+fn lookup_uncached() {
+    cached_process! {
+        static LOOKUP_UNCACHED: Process<Message> = "lookup-uncached-process";
+    }
+
+    assert!(LOOKUP_UNCACHED.lookup_uncached().is_none());
+    assert!(!LOOKUP_UNCACHED.with(|proc| proc.is_looked_up()));
+
+    LOOKUP_UNCACHED.get();
+    let process = spawn_message_process();
+    process.register("lookup-uncached-process");
+    assert_eq!(LOOKUP_UNCACHED.lookup_uncached(), Some(process));
+    assert!(LOOKUP_UNCACHED.with(|proc| proc.is_not_present()));
+    assert_eq!(LOOKUP_UNCACHED.with(|proc| proc.stats().misses), 1);
+}
+
+#[test]
+fn with_resolver() {
+    fn resolve_self(name: &str) -> Option<Process<Message>> {
+        (name == "with-resolver-process").then(Process::this)
+    }
+
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::with_resolver("with-resolver-process", resolve_self);
+    assert_eq!(cache.get(), Some(Process::this()));
+    assert_eq!(cache.lookup_uncached(), Some(Process::this()));
+
+    let missing: ProcessCached<'_, Message> = CachedProcess::builder("with-resolver-missing")
+        .resolver(resolve_self)
+        .build();
+    assert!(missing.get().is_none());
+    assert!(missing.is_not_present());
+}
+
+#[test]
+fn holds() {
+    let cache: ProcessCached<'_, Message> = CachedProcess::new("holds-process");
+    let process = spawn_message_process();
+    assert!(!cache.holds(&process));
+
+    cache.set(process);
+    assert!(cache.holds(&process));
+    assert!(!cache.holds(&spawn_message_process()));
+
+    cache.reset();
+    assert!(!cache.holds(&process));
+}
+
+#[test]
+fn warm_caches() {
+    cached_process! {
+        static WARM_A: Process<Message> = "warm-a-process";
+        static WARM_B: ProcessRef<Message> = "warm-b-process";
+    }
+
+    let process = spawn_message_process();
+    process.register("warm-a-process");
+    assert_eq!(warm(&[&WARM_A, &WARM_B]), 1);
+    assert!(WARM_A.with(|proc| proc.is_present()));
+    assert!(WARM_B.with(|proc| proc.is_not_present()));
+    assert_eq!(WARM_A.with(|proc| proc.stats().misses), 1);
+}
+
+#[test]
+fn last_was_hit() {
+    let cache: ProcessCached<'_, Message> =
+        CachedProcess::with_ttl("last-was-hit-process", Duration::from_millis(10));
+    assert_eq!(cache.last_was_hit(), None);
+
+    cache.set(spawn_message_process());
+    cache.get();
+    assert_eq!(cache.last_was_hit(), Some(true));
+
+    sleep(Duration::from_millis(20));
+    cache.get();
+    assert_eq!(cache.last_was_hit(), Some(false));
+}